@@ -1,66 +1,203 @@
-use crate::game::{ARENA_HEIGHT, ARENA_WIDTH};
-use amethyst::{
-    core::math::{Isometry2, Vector2},
-    prelude::World,
+use crate::{
+    events::ContactEventData,
+    game::BALL_COLLISIONS,
+    level::{Arena, Edge, Side},
 };
+use amethyst::{core::math::Isometry2, ecs::Entity, prelude::World};
+use log::warn;
 use ncollide2d::{
-    pipeline::{CollisionGroups, GeometricQueryType},
+    pipeline::{CollisionGroups, CollisionObjectSlabHandle, GeometricQueryType},
     shape::{Plane, ShapeHandle},
     world::CollisionWorld,
 };
+use std::collections::HashMap;
 
 pub struct CollisionContext {
     pub ball_groups: CollisionGroups,
     pub other_groups: CollisionGroups,
-    pub world: CollisionWorld<f32, ()>,
+    pub power_up_groups: CollisionGroups,
+    pub laser_groups: CollisionGroups,
+    pub arena: Arena,
+    /// Each collision object's data is the entity it belongs to, if any.
+    pub world: CollisionWorld<f32, Option<Entity>>,
+    /// The walls along the edges of the arena, by which side they are on.
+    sides: HashMap<CollisionObjectSlabHandle, Side>,
 }
 
 impl Default for CollisionContext {
     fn default() -> CollisionContext {
         let mut ball_groups = CollisionGroups::new();
         ball_groups.set_membership(&[1]);
+        ball_groups.set_whitelist(&[2]);
 
         let mut other_groups = CollisionGroups::new();
         other_groups.set_membership(&[2]);
-        other_groups.set_whitelist(&[1]);
+        other_groups.set_whitelist(&[1, 3, 4]);
 
-        let world = CollisionWorld::<f32, ()>::new(1.0);
+        let mut power_up_groups = CollisionGroups::new();
+        power_up_groups.set_membership(&[3]);
+        power_up_groups.set_whitelist(&[2]);
+
+        let mut laser_groups = CollisionGroups::new();
+        laser_groups.set_membership(&[4]);
+        laser_groups.set_whitelist(&[2]);
+
+        let world = CollisionWorld::<f32, Option<Entity>>::new(1.0);
 
         CollisionContext {
             ball_groups,
             other_groups,
+            power_up_groups,
+            laser_groups,
+            arena: Arena::default(),
             world,
+            sides: HashMap::new(),
         }
     }
 }
 
-pub fn initialize_collision_context(world: &mut World) {
-    let plane_left = ShapeHandle::new(Plane::new(Vector2::x_axis()));
-    let plane_top = ShapeHandle::new(Plane::new(-Vector2::y_axis()));
-    let plane_right = ShapeHandle::new(Plane::new(-Vector2::x_axis()));
+impl CollisionContext {
+    /// Enables or disables balls bouncing off each other, including balls already in the world.
+    pub fn set_ball_collisions(&mut self, enabled: bool) {
+        self.ball_groups.modify_whitelist(1, enabled);
+
+        let ball_groups = self.ball_groups;
+        for (_, obj) in self.world.objects.iter_mut() {
+            if obj.collision_groups().is_member_of(1) {
+                obj.set_collision_groups(ball_groups);
+            }
+        }
+    }
 
-    let plane_left_pos = Isometry2::new(Vector2::new(0.0, ARENA_HEIGHT / 2.0), 0.0);
-    let plane_top_pos = Isometry2::new(Vector2::new(ARENA_WIDTH / 2.0, ARENA_HEIGHT), 0.0);
-    let plane_right_pos = Isometry2::new(Vector2::new(ARENA_WIDTH, ARENA_HEIGHT / 2.0), 0.0);
+    /// Removes every collision object, leaving only the walls of the current `arena`, e.g.
+    /// before restarting or building the next level.
+    pub fn reset(&mut self) {
+        self.world = CollisionWorld::new(1.0);
+        self.sides.clear();
+        self.add_boundaries();
+    }
 
-    let mut collision_context = CollisionContext::default();
+    /// The entity owning the collision object, if it belongs to one.
+    pub fn entity(&self, handle: CollisionObjectSlabHandle) -> Option<Entity> {
+        self.world
+            .collision_object(handle)
+            .and_then(|obj| *obj.data())
+    }
+
+    /// Which side of the arena the collision object walls off, if it is one of the arena's
+    /// edges rather than a wall inside it or something else.
+    pub fn side(&self, handle: CollisionObjectSlabHandle) -> Option<Side> {
+        self.sides.get(&handle).copied()
+    }
+
+    /// Adds the arena's walls. Lethal edges are left to the `BallDestroyer`s.
+    fn add_boundaries(&mut self) {
+        let other_groups = self.other_groups;
+        let contact_query = GeometricQueryType::Contacts(0.0, 0.0);
+
+        for &(side, edge, normal, position) in self.arena.edges().iter() {
+            if edge == Edge::Wall {
+                let plane = ShapeHandle::new(Plane::new(normal));
+                let position = Isometry2::new(position, 0.0);
+                let (handle, _) =
+                    self.world
+                        .add(position, plane, other_groups, contact_query, None);
+                self.sides.insert(handle, side);
+            }
+        }
+
+        for wall in self.arena.walls.iter() {
+            match wall.shape() {
+                Some(shape) => {
+                    self.world.add(
+                        Isometry2::identity(),
+                        shape,
+                        other_groups,
+                        contact_query,
+                        None,
+                    );
+                }
+                None => warn!("Skipping wall without a shape: {:?}", wall),
+            }
+        }
+    }
+}
+
+/// A contact that has started but not yet stopped.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ActiveContact {
+    pub you_handle: CollisionObjectSlabHandle,
+    pub other_handle: CollisionObjectSlabHandle,
+    pub other: Option<Entity>,
+}
+
+/// Every entity's ongoing contacts, kept up to date by the `WorldUpdateSystem`.
+#[derive(Debug, Default)]
+pub struct ActiveContacts {
+    contacts: HashMap<Entity, Vec<ActiveContact>>,
+}
+
+impl ActiveContacts {
+    pub fn get(&self, entity: Entity) -> &[ActiveContact] {
+        self.contacts
+            .get(&entity)
+            .map(|contacts| &contacts[..])
+            .unwrap_or(&[])
+    }
 
-    let other_groups = collision_context.other_groups;
-    let contact_query = GeometricQueryType::Contacts(0.0, 0.0);
-
-    collision_context
-        .world
-        .add(plane_left_pos, plane_left, other_groups, contact_query, ());
-    collision_context
-        .world
-        .add(plane_top_pos, plane_top, other_groups, contact_query, ());
-    collision_context.world.add(
-        plane_right_pos,
-        plane_right,
-        other_groups,
-        contact_query,
-        (),
-    );
+    pub fn apply(&mut self, event: &ContactEventData) {
+        match *event {
+            ContactEventData::Started {
+                you_handle,
+                other_handle,
+                you,
+                other,
+            } => {
+                let contact = ActiveContact {
+                    you_handle,
+                    other_handle,
+                    other,
+                };
+                let contacts = self.contacts.entry(you).or_insert_with(Vec::new);
+                if !contacts.contains(&contact) {
+                    contacts.push(contact);
+                }
+            }
+            ContactEventData::Stopped {
+                you_handle,
+                other_handle,
+                you,
+                ..
+            } => {
+                if let Some(contacts) = self.contacts.get_mut(&you) {
+                    contacts.retain(|contact| {
+                        contact.you_handle != you_handle || contact.other_handle != other_handle
+                    });
+                    if contacts.is_empty() {
+                        self.contacts.remove(&you);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Drops contacts for which `keep` returns false, e.g. because one side has been removed
+    /// from the world without a `Stopped` event being generated.
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(Entity, &ActiveContact) -> bool,
+    {
+        self.contacts.retain(|&entity, contacts| {
+            contacts.retain(|contact| keep(entity, contact));
+            !contacts.is_empty()
+        });
+    }
+}
+
+pub fn initialize_collision_context(world: &mut World) {
+    let mut collision_context = CollisionContext::default();
+    collision_context.set_ball_collisions(BALL_COLLISIONS);
+    collision_context.add_boundaries();
 
     world.insert(collision_context);
 }
@@ -1,89 +1,203 @@
-use crate::collision::CollisionContext;
+use crate::{
+    collision::CollisionContext,
+    game::{PADDLE_HEIGHT, PADDLE_WIDTH},
+    level::Path,
+};
 use amethyst::{
-    core::{ecs::Entity, math::Vector2, transform::Transform},
-    ecs::{Component, DenseVecStorage, NullStorage},
+    core::{
+        math::{Vector2, Vector3},
+        transform::Transform,
+    },
+    ecs::{Component, DenseVecStorage, Entity, NullStorage},
     prelude::{World, WorldExt},
 };
 use ncollide2d::{
     math::Isometry,
     pipeline::{CollisionGroups, CollisionObjectSlabHandle, GeometricQueryType},
-    shape::{Shape, ShapeHandle},
+    shape::{Cuboid, Shape, ShapeHandle},
 };
-use smallvec::SmallVec;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Default)]
-pub struct Paddle;
+#[derive(Debug, Clone)]
+pub struct Paddle {
+    pub width: f32,
+}
 
 #[derive(Debug, Clone)]
 pub struct Ball {
     pub velocity: Vector2<f32>,
 }
 
-#[derive(Clone)]
-pub struct Collidable {
-    pub handle: CollisionObjectSlabHandle,
+/// Marks a ball that is being held on the paddle by the sticky paddle power-up.
+#[derive(Debug, Clone)]
+pub struct Stuck {
+    pub offset: f32,
+    pub speed: f32,
 }
 
-#[derive(Copy, Clone)]
-pub enum ContactEventData {
-    Started {
-        you_handle: CollisionObjectSlabHandle,
-        other_handle: CollisionObjectSlabHandle,
-        you: Entity,
-        other: Option<Entity>,
-    },
-    Stopped {
-        you_handle: CollisionObjectSlabHandle,
-        other_handle: CollisionObjectSlabHandle,
-        you: Entity,
-        other: Option<Entity>,
-    },
+#[derive(Debug, Clone)]
+pub struct Brick {
+    /// Which row of the grid the brick is in, counted from the top.
+    pub row: usize,
+    /// Whether balls and lasers can break the brick. A level is cleared once every destructible
+    /// brick is gone.
+    pub destructible: bool,
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum PowerUpKind {
+    MultiBall,
+    WidePaddle,
+    SlowBall,
+    StickyPaddle,
+    Laser,
+}
+
+#[derive(Debug, Clone)]
+pub struct PowerUp {
+    pub kind: PowerUpKind,
+}
+
+#[derive(Debug, Default)]
+pub struct Laser;
+
 #[derive(Clone)]
-pub struct Contact {
-    pub contacts: SmallVec<[ContactEventData; 2]>,
+pub struct Collidable {
+    pub handle: CollisionObjectSlabHandle,
 }
 
 #[derive(Debug, Default)]
 pub struct BallDestroyer;
 
+/// Moves a brick or obstacle along a path from where it started.
+#[derive(Debug, Clone)]
+pub struct PathFollower {
+    pub path: Path,
+    /// Radians per second.
+    pub spin: f32,
+    pub origin: Vector2<f32>,
+    pub elapsed: f32,
+    /// How fast it moved over the last frame, so things bouncing off of it can take it into
+    /// account.
+    pub velocity: Vector2<f32>,
+}
+
 impl Component for Paddle {
-    type Storage = NullStorage<Paddle>;
+    type Storage = DenseVecStorage<Paddle>;
 }
 
 impl Component for Ball {
     type Storage = DenseVecStorage<Ball>;
 }
 
-impl Component for Collidable {
-    type Storage = DenseVecStorage<Collidable>;
+impl Component for Stuck {
+    type Storage = DenseVecStorage<Stuck>;
+}
+
+impl Component for Brick {
+    type Storage = DenseVecStorage<Brick>;
 }
 
-impl Component for Contact {
-    type Storage = DenseVecStorage<Contact>;
+impl Component for PowerUp {
+    type Storage = DenseVecStorage<PowerUp>;
+}
+
+impl Component for Laser {
+    type Storage = NullStorage<Laser>;
+}
+
+impl Component for Collidable {
+    type Storage = DenseVecStorage<Collidable>;
 }
 
 impl Component for BallDestroyer {
     type Storage = NullStorage<BallDestroyer>;
 }
 
+impl Component for PathFollower {
+    type Storage = DenseVecStorage<PathFollower>;
+}
+
+impl Paddle {
+    pub fn new(width: f32) -> Paddle {
+        Paddle { width }
+    }
+
+    /// Changes the width of the paddle, keeping its sprite scale and collision shape in sync.
+    pub fn set_width(
+        &mut self,
+        width: f32,
+        transform: &mut Transform,
+        collidable: &Collidable,
+        context: &mut CollisionContext,
+    ) {
+        self.width = width;
+
+        let scale = transform.scale();
+        let scale = Vector3::new(width / PADDLE_WIDTH, scale.y, scale.z);
+        transform.set_scale(scale);
+
+        let shape = Cuboid::new(Vector2::new(width / 2.0, PADDLE_HEIGHT / 2.0));
+        let obj = context.world.get_mut(collidable.handle).unwrap();
+        obj.set_shape(ShapeHandle::new(shape));
+    }
+
+    /// The range the center of the paddle may move in without leaving the arena.
+    pub fn bounds(&self, arena_width: f32) -> (f32, f32) {
+        (self.width / 2.0, arena_width - self.width / 2.0)
+    }
+}
+
 impl Collidable {
-    pub fn new_ball<S: Shape<f32>>(world: &World, transform: &Transform, shape: S) -> Collidable {
+    pub fn new_ball<S: Shape<f32>>(
+        world: &World,
+        entity: Entity,
+        transform: &Transform,
+        shape: S,
+    ) -> Collidable {
         let context = &mut *world.write_resource::<CollisionContext>();
 
-        Collidable::new(context, context.ball_groups, transform, shape)
+        Collidable::new(context, context.ball_groups, entity, transform, shape)
     }
 
-    pub fn new_other<S: Shape<f32>>(world: &World, transform: &Transform, shape: S) -> Collidable {
+    pub fn new_other<S: Shape<f32>>(
+        world: &World,
+        entity: Entity,
+        transform: &Transform,
+        shape: S,
+    ) -> Collidable {
         let context = &mut *world.write_resource::<CollisionContext>();
 
-        Collidable::new(context, context.other_groups, transform, shape)
+        Collidable::new(context, context.other_groups, entity, transform, shape)
+    }
+
+    pub fn new_power_up<S: Shape<f32>>(
+        world: &World,
+        entity: Entity,
+        transform: &Transform,
+        shape: S,
+    ) -> Collidable {
+        let context = &mut *world.write_resource::<CollisionContext>();
+
+        Collidable::new(context, context.power_up_groups, entity, transform, shape)
+    }
+
+    pub fn new_laser<S: Shape<f32>>(
+        world: &World,
+        entity: Entity,
+        transform: &Transform,
+        shape: S,
+    ) -> Collidable {
+        let context = &mut *world.write_resource::<CollisionContext>();
+
+        Collidable::new(context, context.laser_groups, entity, transform, shape)
     }
 
     fn new<S: Shape<f32>>(
         context: &mut CollisionContext,
         group: CollisionGroups,
+        entity: Entity,
         transform: &Transform,
         shape: S,
     ) -> Collidable {
@@ -97,9 +211,43 @@ impl Collidable {
             handle,
             group,
             GeometricQueryType::Contacts(0.0, 0.0),
-            (),
+            Some(entity),
         );
 
         Collidable { handle }
     }
 }
+
+impl PathFollower {
+    pub fn new(path: Path, spin: f32, origin: Vector2<f32>) -> PathFollower {
+        PathFollower {
+            path,
+            spin,
+            origin,
+            elapsed: 0.0,
+            velocity: Vector2::zeros(),
+        }
+    }
+}
+
+impl PowerUpKind {
+    pub fn random<R: Rng + ?Sized>(rng: &mut R) -> PowerUpKind {
+        match rng.gen_range(0, 5) {
+            0 => PowerUpKind::MultiBall,
+            1 => PowerUpKind::WidePaddle,
+            2 => PowerUpKind::SlowBall,
+            3 => PowerUpKind::StickyPaddle,
+            _ => PowerUpKind::Laser,
+        }
+    }
+
+    pub fn sprite_number(self) -> usize {
+        match self {
+            PowerUpKind::MultiBall => 2,
+            PowerUpKind::WidePaddle => 4,
+            PowerUpKind::SlowBall => 6,
+            PowerUpKind::StickyPaddle => 8,
+            PowerUpKind::Laser => 10,
+        }
+    }
+}
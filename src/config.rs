@@ -0,0 +1,200 @@
+use crate::game::PADDLE_MAX_VELOCITY;
+use amethyst::config::{Config, ConfigError};
+use serde::{Deserialize, Serialize};
+use std::{io, path::Path};
+
+/// Gameplay tuning loaded from `config/gameplay.ron`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameplayConfig {
+    pub speed_policy: SpeedPolicy,
+    pub paddle_control: PaddleControl,
+    /// The shallowest angle from horizontal, in degrees, a ball may leave a bounce at.
+    pub min_bounce_angle: f32,
+    /// How long, in seconds, a ball may go without touching the paddle or a brick before it is
+    /// nudged onto a new path.
+    pub stuck_timeout: f32,
+    /// How many bounces in a row between the same two surfaces count as the ball being stuck.
+    pub stuck_bounces: usize,
+    /// The largest angle, in degrees, a stuck ball is turned by when it is nudged.
+    pub stuck_nudge_angle: f32,
+    pub autoplay: AutoplayConfig,
+}
+
+impl Default for GameplayConfig {
+    fn default() -> GameplayConfig {
+        GameplayConfig {
+            speed_policy: SpeedPolicy::HitRamp {
+                initial: 180.0,
+                per_hit: 6.0,
+                max: 540.0,
+            },
+            paddle_control: PaddleControl::Proportional {
+                max_velocity: PADDLE_MAX_VELOCITY,
+            },
+            min_bounce_angle: 15.0,
+            stuck_timeout: 10.0,
+            stuck_bounces: 6,
+            stuck_nudge_angle: 20.0,
+            autoplay: AutoplayConfig::default(),
+        }
+    }
+}
+
+impl GameplayConfig {
+    /// Loads the config, rejecting one that couldn't keep the balls moving.
+    pub fn load_checked<P: AsRef<Path>>(path: P) -> Result<GameplayConfig, ConfigError> {
+        let config = GameplayConfig::load(path)?;
+        config.speed_policy.check().map_err(|message| {
+            ConfigError::File(io::Error::new(io::ErrorKind::InvalidData, message))
+        })?;
+        Ok(config)
+    }
+}
+
+/// How well the bot plays.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoplayConfig {
+    /// How often, in seconds, the bot looks at where the balls are going.
+    pub reaction_delay: f32,
+    /// How far, in either direction, the bot may misjudge where a ball will land.
+    pub prediction_error: f32,
+}
+
+impl Default for AutoplayConfig {
+    fn default() -> AutoplayConfig {
+        AutoplayConfig {
+            reaction_delay: 0.1,
+            prediction_error: 16.0,
+        }
+    }
+}
+
+/// How fast the ball should be going, given how far into the game the player is.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SpeedPolicy {
+    /// Speeds up steadily with the time since the ball was served.
+    TimeRamp {
+        initial: f32,
+        per_second: f32,
+        max: f32,
+    },
+    /// Speeds up by a fixed amount every time the ball hits the paddle or a brick.
+    HitRamp {
+        initial: f32,
+        per_hit: f32,
+        max: f32,
+    },
+    /// A fixed speed for each level, the last one being used for any levels past the end.
+    LevelTable { speeds: Vec<f32> },
+    /// Classic Breakout: steps up after a number of hits, when the ball first reaches certain
+    /// rows of bricks (counted from the top) and when it first breaks through to the ceiling.
+    Breakout {
+        initial: f32,
+        step: f32,
+        hit_thresholds: Vec<u32>,
+        row_thresholds: Vec<usize>,
+        ceiling: bool,
+    },
+}
+
+/// How the paddle responds to the player.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PaddleControl {
+    /// Jumps straight to the mouse.
+    Instant,
+    /// Heads for the mouse at a speed proportional to how far away it is, up to `max_velocity`.
+    /// How quickly it closes the distance is the player's paddle smoothing setting.
+    Proportional { max_velocity: f32 },
+    /// Driven by the left and right keys rather than the mouse. Holding one accelerates the
+    /// paddle that way, up to `max_velocity`, and letting go lets friction slow it down.
+    Acceleration {
+        acceleration: f32,
+        friction: f32,
+        max_velocity: f32,
+    },
+}
+
+/// What the speed policies base the ball's speed on, reset every time a new ball is served.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SpeedProgress {
+    pub elapsed: f32,
+    pub hits: u32,
+    pub level: usize,
+    pub highest_row: Option<usize>,
+    pub ceiling_hit: bool,
+}
+
+impl SpeedPolicy {
+    /// Checks that the policy can always come up with a speed to set.
+    pub fn check(&self) -> Result<(), String> {
+        match self {
+            SpeedPolicy::LevelTable { speeds } if speeds.is_empty() => {
+                Err("The level table speed policy has no speeds".to_string())
+            }
+            SpeedPolicy::LevelTable { speeds } => {
+                match speeds
+                    .iter()
+                    .find(|speed| !speed.is_finite() || **speed <= 0.0)
+                {
+                    Some(speed) => Err(format!(
+                        "The level table speed policy has a speed of {}, but speeds must be \
+                         positive",
+                        speed
+                    )),
+                    None => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The speed to set every ball to, or `None` if the policy has none to give and the balls
+    /// should be left as they are.
+    pub fn speed(&self, progress: &SpeedProgress) -> Option<f32> {
+        match self {
+            SpeedPolicy::TimeRamp {
+                initial,
+                per_second,
+                max,
+            } => Some((initial + per_second * progress.elapsed).min(*max)),
+            SpeedPolicy::HitRamp {
+                initial,
+                per_hit,
+                max,
+            } => Some((initial + per_hit * progress.hits as f32).min(*max)),
+            SpeedPolicy::LevelTable { speeds } => speeds
+                .get(progress.level)
+                .or_else(|| speeds.last())
+                .copied(),
+            SpeedPolicy::Breakout {
+                initial,
+                step,
+                hit_thresholds,
+                row_thresholds,
+                ceiling,
+            } => {
+                let hit_steps = hit_thresholds
+                    .iter()
+                    .filter(|&&threshold| progress.hits >= threshold)
+                    .count();
+                let row_steps = row_thresholds
+                    .iter()
+                    .filter(|&&threshold| {
+                        progress
+                            .highest_row
+                            .map_or(false, |highest_row| highest_row <= threshold)
+                    })
+                    .count();
+                let ceiling_steps = if *ceiling && progress.ceiling_hit {
+                    1
+                } else {
+                    0
+                };
+
+                Some(initial + step * (hit_steps + row_steps + ceiling_steps) as f32)
+            }
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use crate::{
+    components::{Ball, Brick, Paddle},
+    config::GameplayConfig,
+    events::GameplayEvent,
+    game::{
+        LaunchOptions, ARENA_HEIGHT, ARENA_WIDTH, BALL_MAX_SPEED, BRICK_COLUMNS, BRICK_HEIGHT,
+        BRICK_POINTS, BRICK_ROWS, BRICK_SPACING, BRICK_TOP_MARGIN, BRICK_WIDTH,
+    },
+    headless::Simulation,
+    level::{Campaign, Progress},
+    systems::{Controller, PaddleInput},
+};
+use amethyst::{
+    core::{math::Vector2, transform::Transform},
+    ecs::Join,
+    prelude::{GameDataBuilder, WorldExt},
+    shrev::{EventChannel, ReaderId},
+};
+use std::path::Path;
+
+/// A game an agent can learn to play, one step at a time, in the style of OpenAI Gym.
+pub trait Env {
+    type Observation;
+    type Action;
+
+    /// Starts a new episode from the given seed.
+    fn reset(&mut self, seed: u64) -> Self::Observation;
+
+    /// Carries out the action for one step, returning what the agent sees afterwards, the reward
+    /// for the step and whether the episode is over.
+    fn step(&mut self, action: Self::Action) -> (Self::Observation, f32, bool);
+}
+
+/// Which way to move the paddle for a step.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Action {
+    Left,
+    Stay,
+    Right,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BallObservation {
+    pub position: Vector2<f32>,
+    pub velocity: Vector2<f32>,
+}
+
+/// What the agent sees of the game after each step. Positions are in arena coordinates.
+#[derive(Debug, Clone, Default)]
+pub struct Observation {
+    pub paddle_x: f32,
+    pub paddle_width: f32,
+    /// Every ball in play, lowest first.
+    pub balls: Vec<BallObservation>,
+    /// The brick grid, row by row from the top, `BRICK_COLUMNS` to a row. Each cell is 0 when
+    /// it's empty, 1 for a destructible brick and -1 for an indestructible one. Moving bricks are
+    /// put in whichever cell they are over.
+    pub bricks: Vec<f32>,
+}
+
+/// How many balls `Observation::to_vec` includes.
+pub const OBSERVED_BALLS: usize = 3;
+
+impl Observation {
+    /// The observation as a fixed length vector of values roughly between -1 and 1, for feeding
+    /// to a network. Balls beyond the first `OBSERVED_BALLS` are left out, and missing ones are
+    /// zeros.
+    pub fn to_vec(&self) -> Vec<f32> {
+        let mut values = vec![self.paddle_x / ARENA_WIDTH, self.paddle_width / ARENA_WIDTH];
+
+        for i in 0..OBSERVED_BALLS {
+            let ball = self.balls.get(i).cloned().unwrap_or_default();
+            values.extend_from_slice(&[
+                ball.position.x / ARENA_WIDTH,
+                ball.position.y / ARENA_HEIGHT,
+                ball.velocity.x / BALL_MAX_SPEED,
+                ball.velocity.y / BALL_MAX_SPEED,
+            ]);
+        }
+
+        values.extend_from_slice(&self.bricks);
+        values
+    }
+}
+
+/// Points lost for every ball that goes out of play, on top of the points scored for bricks.
+const BALL_LOST_REWARD: f32 = -100.0;
+
+/// The headless game as an `Env`. The agent moves the paddle, and balls are let go of and
+/// lasers fired as soon as they can be. An episode lasts until the game is over or the level is
+/// cleared.
+pub struct BreakoutEnv {
+    simulation: Simulation,
+    reader: ReaderId<GameplayEvent>,
+}
+
+impl BreakoutEnv {
+    /// `game_data` should hold the gameplay systems and no rendering; see
+    /// `crate::gameplay_systems`.
+    pub fn new(
+        assets_dir: &Path,
+        game_data: GameDataBuilder<'static, 'static>,
+        gameplay_config: GameplayConfig,
+        campaign: Campaign,
+    ) -> amethyst::Result<BreakoutEnv> {
+        let mut simulation = Simulation::new(
+            assets_dir,
+            game_data,
+            gameplay_config,
+            campaign,
+            LaunchOptions::default(),
+        )?;
+        *simulation.world.write_resource::<Controller>() = Controller::External;
+
+        let reader = simulation
+            .world
+            .fetch_mut::<EventChannel<GameplayEvent>>()
+            .register_reader();
+
+        Ok(BreakoutEnv { simulation, reader })
+    }
+
+    fn observe(&self) -> Observation {
+        let world = &self.simulation.world;
+        let transforms = world.read_storage::<Transform>();
+
+        let (paddle_x, paddle_width) = (&world.read_storage::<Paddle>(), &transforms)
+            .join()
+            .next()
+            .map(|(paddle, transform)| (transform.translation().x, paddle.width))
+            .unwrap_or_default();
+
+        let mut balls: Vec<BallObservation> = (&world.read_storage::<Ball>(), &transforms)
+            .join()
+            .map(|(ball, transform)| {
+                let translation = transform.translation();
+                BallObservation {
+                    position: Vector2::new(translation.x, translation.y),
+                    velocity: ball.velocity,
+                }
+            })
+            .collect();
+        balls.sort_by(|a, b| a.position.y.total_cmp(&b.position.y));
+
+        // the grid a full level is laid out on
+        let (pitch_x, pitch_y) = (BRICK_WIDTH + BRICK_SPACING, BRICK_HEIGHT + BRICK_SPACING);
+        let left = (ARENA_WIDTH - BRICK_COLUMNS as f32 * pitch_x + BRICK_SPACING) / 2.0
+            + BRICK_WIDTH / 2.0;
+        let top = ARENA_HEIGHT - BRICK_TOP_MARGIN - BRICK_HEIGHT / 2.0;
+
+        let mut bricks = vec![0.0; BRICK_ROWS * BRICK_COLUMNS];
+        for (brick, transform) in (&world.read_storage::<Brick>(), &transforms).join() {
+            let translation = transform.translation();
+            let column = ((translation.x - left) / pitch_x).round();
+            let row = ((top - translation.y) / pitch_y).round();
+            if column < 0.0
+                || row < 0.0
+                || column >= BRICK_COLUMNS as f32
+                || row >= BRICK_ROWS as f32
+            {
+                continue;
+            }
+
+            bricks[row as usize * BRICK_COLUMNS + column as usize] =
+                if brick.destructible { 1.0 } else { -1.0 };
+        }
+
+        Observation {
+            paddle_x,
+            paddle_width,
+            balls,
+            bricks,
+        }
+    }
+}
+
+impl Env for BreakoutEnv {
+    type Observation = Observation;
+    type Action = Action;
+
+    fn reset(&mut self, seed: u64) -> Observation {
+        self.simulation.reset(seed);
+
+        // anything from the last episode is no longer of interest
+        self.simulation
+            .world
+            .read_resource::<EventChannel<GameplayEvent>>()
+            .read(&mut self.reader)
+            .for_each(|_| ());
+
+        self.observe()
+    }
+
+    fn step(&mut self, action: Action) -> (Observation, f32, bool) {
+        let paddle_x = {
+            let world = &self.simulation.world;
+            (
+                &world.read_storage::<Paddle>(),
+                &world.read_storage::<Transform>(),
+            )
+                .join()
+                .next()
+                .map_or(ARENA_WIDTH / 2.0, |(_, transform)| {
+                    transform.translation().x
+                })
+        };
+        let (target_x, direction) = match action {
+            // far enough away for the paddle to go flat out
+            Action::Left => (0.0, -1.0),
+            Action::Stay => (paddle_x, 0.0),
+            Action::Right => (ARENA_WIDTH, 1.0),
+        };
+        *self.simulation.world.write_resource::<PaddleInput>() = PaddleInput {
+            target_x,
+            direction,
+            release: true,
+            fire: true,
+        };
+
+        self.simulation.step();
+
+        let world = &self.simulation.world;
+        let mut reward = 0.0;
+        let mut done = false;
+        for event in world
+            .read_resource::<EventChannel<GameplayEvent>>()
+            .read(&mut self.reader)
+        {
+            match event {
+                GameplayEvent::BrickDestroyed { .. } => {
+                    reward += BRICK_POINTS as f32;
+
+                    // the game moves on to the next level next step
+                    done |= !world
+                        .read_storage::<Brick>()
+                        .join()
+                        .any(|brick| brick.destructible);
+                }
+                GameplayEvent::BallLost { .. } => reward += BALL_LOST_REWARD,
+                GameplayEvent::LastBallLost => {
+                    // the game handles it next step, ending it if there are no lives left
+                    done |= world.read_resource::<Progress>().lives == 0;
+                }
+                _ => {}
+            }
+        }
+
+        (self.observe(), reward, done)
+    }
+}
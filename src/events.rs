@@ -0,0 +1,51 @@
+use crate::level::Side;
+use amethyst::{core::math::Vector2, ecs::Entity};
+use ncollide2d::pipeline::CollisionObjectSlabHandle;
+
+/// A contact starting or stopping between two collision objects, published by the
+/// `WorldUpdateSystem` once for each side of the pair.
+#[derive(Debug, Copy, Clone)]
+pub enum ContactEventData {
+    Started {
+        you_handle: CollisionObjectSlabHandle,
+        other_handle: CollisionObjectSlabHandle,
+        you: Entity,
+        other: Option<Entity>,
+    },
+    Stopped {
+        you_handle: CollisionObjectSlabHandle,
+        other_handle: CollisionObjectSlabHandle,
+        you: Entity,
+        other: Option<Entity>,
+    },
+}
+
+/// Something that happened in the game that other features (audio, scoring, particles, ...) may
+/// want to react to.
+#[derive(Debug, Copy, Clone)]
+pub enum GameplayEvent {
+    BallHitPaddle {
+        ball: Entity,
+        paddle: Entity,
+    },
+    BallHitWall {
+        ball: Entity,
+        /// Points from the ball towards the wall.
+        normal: Vector2<f32>,
+        /// Which edge of the arena the wall is along, or `None` for a wall inside the arena.
+        side: Option<Side>,
+    },
+    BallHitBrick {
+        ball: Entity,
+        brick: Entity,
+    },
+    BallLost {
+        ball: Entity,
+    },
+    /// Raised after `BallLost` once the last ball in play has been destroyed.
+    LastBallLost,
+    BrickDestroyed {
+        brick: Entity,
+        position: Vector2<f32>,
+    },
+}
@@ -1,17 +1,42 @@
 use crate::{
-    collision::initialize_collision_context,
-    components::{Ball, BallDestroyer, Collidable, Paddle},
+    collision::{initialize_collision_context, ActiveContacts, CollisionContext},
+    components::{
+        Ball, BallDestroyer, Brick, Collidable, Laser, Paddle, PathFollower, PowerUp, PowerUpKind,
+        Stuck,
+    },
+    config::{GameplayConfig, SpeedProgress},
+    events::GameplayEvent,
+    high_score::{HighScore, HighScoreState},
+    level::{Arena, Campaign, Edge, Level, Progress, Wall},
+    replay::{Recording, Replay},
+    save::SaveGame,
+    settings::{apply_settings, OptionsState, Settings},
+    systems::{ActivePowerUps, Controller, PaddleInput},
 };
 use amethyst::{
     assets::{AssetStorage, Handle, Loader},
-    core::{math::Vector2, transform::Transform},
-    prelude::{Builder, World, WorldExt},
-    renderer::{Camera, ImageFormat, SpriteRender, SpriteSheet, SpriteSheetFormat, Texture},
+    core::{
+        math::{Point3, Vector2, Vector3},
+        timing::Time,
+        transform::Transform,
+    },
+    ecs::{Entity, Join},
+    input::{is_close_requested, is_key_down},
+    prelude::{Builder, SimpleTrans, StateEvent, Trans, World, WorldExt},
+    renderer::{
+        debug_drawing::DebugLinesComponent, palette::Srgba, resources::Tint, Camera, ImageFormat,
+        SpriteRender, SpriteSheet, SpriteSheetFormat, Texture,
+    },
+    shrev::{EventChannel, ReaderId},
+    ui::{Anchor, TtfFormat, UiText, UiTransform},
+    winit::{ElementState, Event, WindowEvent},
     GameData, SimpleState, StateData,
 };
-use ncollide2d::shape::{Cuboid, Plane};
-use rand::{thread_rng, Rng};
-use std::f32::consts::PI;
+use log::{error, info};
+use ncollide2d::shape::{Ball as BallShape, Cuboid, Plane};
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+use std::{f32::consts::PI, path::PathBuf};
 
 pub const ARENA_WIDTH: f32 = 1280.0;
 pub const ARENA_HEIGHT: f32 = 720.0;
@@ -21,13 +46,253 @@ pub const PADDLE_MAX_VELOCITY: f32 = 720.0;
 pub const PADDLE_DISTANCE_VELOCITY_RATIO: f32 = 1.0 / 6.0;
 pub const BALL_WIDTH: f32 = 16.0;
 pub const BALL_HEIGHT: f32 = 16.0;
+pub const BALL_RADIUS: f32 = BALL_WIDTH / 2.0;
 pub const BALL_INITIAL_SPEED: f32 = 60.0;
 pub const BALL_MAX_SPEED: f32 = 540.0;
 pub const BALL_MAX_ROTATION: f32 = PI / 12.0;
-pub const BALL_MIN_SCALE: f32 = 1.0;
-pub const BALL_MAX_SCALE: f32 = 1.1;
+pub const BALL_MAX_RELEASE_ANGLE: f32 = PI / 3.0;
+pub const BALL_COLLISIONS: bool = false;
+pub const BRICK_WIDTH: f32 = 48.0;
+pub const BRICK_HEIGHT: f32 = 24.0;
+pub const BRICK_ROWS: usize = 9;
+pub const BRICK_COLUMNS: usize = 22;
+pub const BRICK_SPACING: f32 = 8.0;
+pub const BRICK_TOP_MARGIN: f32 = 64.0;
+pub const BRICK_POINTS: u32 = 10;
+pub const STARTING_LIVES: u32 = 3;
+pub const HIGH_SCORE_COUNT: usize = 10;
+pub const HIGH_SCORE_NAME_LENGTH: usize = 12;
+pub const POWER_UP_CHANCE: f64 = 0.15;
+pub const POWER_UP_WIDTH: f32 = 24.0;
+pub const POWER_UP_HEIGHT: f32 = 12.0;
+pub const POWER_UP_FALL_SPEED: f32 = 120.0;
+pub const POWER_UP_DURATION: f32 = 15.0;
+pub const MULTI_BALL_COUNT: usize = 2;
+pub const MULTI_BALL_SPREAD: f32 = PI / 8.0;
+pub const WIDE_PADDLE_SCALE: f32 = 1.5;
+pub const SLOW_BALL_MAX_SPEED: f32 = 120.0;
+pub const LASER_WIDTH: f32 = 4.0;
+pub const LASER_HEIGHT: f32 = 16.0;
+pub const LASER_SPEED: f32 = 720.0;
+pub const LASER_COOLDOWN: f32 = 0.5;
+pub const PORTAL_SEGMENTS: u32 = 24;
+pub const VSYNC_FRAME_RATE: u32 = 60;
+pub const HEADLESS_STEP: f32 = 1.0 / 60.0;
+pub const BENCH_BALLS: usize = 64;
+/// Which of the seed's PCG streams the bot draws from, apart from the game's.
+pub const BOT_RNG_STREAM: u64 = 1;
 
-pub struct GameState;
+/// The sprite sheet shared by everything in the game, kept around so systems can spawn entities.
+#[derive(Clone)]
+pub struct GameSprites {
+    pub sprite_sheet: Handle<SpriteSheet>,
+}
+
+/// The random number generator all gameplay randomness is drawn from, so that a game can be
+/// played out again from the same seed.
+pub struct GameRng {
+    pub seed: u64,
+    pub rng: Pcg32,
+}
+
+impl GameRng {
+    pub fn from_seed(seed: u64) -> GameRng {
+        GameRng {
+            seed,
+            rng: Pcg32::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> GameRng {
+        GameRng::from_seed(rand::random())
+    }
+}
+
+/// The bot's own random number generator, seeded along with the `GameRng`. Drawing from it
+/// leaves the game's draws the same whether or not the bot is playing, so a recording of the
+/// bot plays out the same without it.
+pub struct BotRng(pub Pcg32);
+
+impl BotRng {
+    pub fn from_seed(seed: u64) -> BotRng {
+        BotRng(Pcg32::new(seed, BOT_RNG_STREAM))
+    }
+}
+
+impl Default for BotRng {
+    fn default() -> BotRng {
+        BotRng::from_seed(rand::random())
+    }
+}
+
+/// How the game was asked to start, e.g. from the command line. With none of these, a saved game
+/// is picked back up, or else the demo plays until the player starts a game.
+#[derive(Debug, Default)]
+pub struct LaunchOptions {
+    /// Start a game straight away on this level, counting from zero.
+    pub level: Option<usize>,
+    pub seed: Option<u64>,
+    /// Play out a recorded game instead of letting anyone play.
+    pub replay: Option<Replay>,
+    /// Record the game, to play out again later.
+    pub record: Option<PathBuf>,
+}
+
+/// A replay being played out, and the next frame of it to play.
+struct Playback {
+    replay: Replay,
+    next: usize,
+}
+
+#[derive(Default)]
+pub struct GameState {
+    options: LaunchOptions,
+    gameplay_reader: Option<ReaderId<GameplayEvent>>,
+    /// Set while the high scores are shown after game over, so the demo starts again on return.
+    game_over: bool,
+    /// The bot is playing a demo behind the title until the player presses something.
+    attract: bool,
+    /// Running without a window: nothing is saved or shown, and unless a replay is being played
+    /// the bot plays game after game.
+    headless: bool,
+    title: Option<Entity>,
+    playback: Option<Playback>,
+}
+
+impl GameState {
+    pub fn new(options: LaunchOptions) -> GameState {
+        GameState {
+            options,
+            ..GameState::default()
+        }
+    }
+
+    pub fn headless(options: LaunchOptions) -> GameState {
+        GameState {
+            options,
+            headless: true,
+            ..GameState::default()
+        }
+    }
+
+    /// Whether a replay was being played and has run out of frames, or its game is over.
+    pub fn replay_finished(&self) -> bool {
+        self.playback.as_ref().map_or(false, |playback| {
+            playback.next >= playback.replay.frames.len()
+        })
+    }
+
+    /// Whether the player is playing a game of their own, which is worth saving.
+    fn is_player_game(&self) -> bool {
+        !self.attract && !self.headless && self.playback.is_none()
+    }
+
+    /// Throws away the game in progress, along with any of its events not yet handled, and starts
+    /// a new one.
+    pub fn restart(&mut self, world: &mut World, rng: GameRng) {
+        if let Some(reader) = self.gameplay_reader.as_mut() {
+            world
+                .read_resource::<EventChannel<GameplayEvent>>()
+                .read(reader)
+                .for_each(|_| ());
+        }
+
+        new_game(world, rng, 0);
+    }
+
+    /// Has the bot play behind the title.
+    fn start_attract(&mut self, world: &mut World) {
+        self.attract = true;
+        *world.write_resource::<Controller>() = Controller::Bot;
+        new_game(world, GameRng::default(), 0);
+
+        let font = world.read_resource::<Loader>().load(
+            "font/square.ttf",
+            TtfFormat,
+            (),
+            &world.read_resource(),
+        );
+        let transform = UiTransform::new(
+            "title".to_string(),
+            Anchor::Middle,
+            Anchor::Middle,
+            0.0,
+            -120.0,
+            1.0,
+            960.0,
+            40.0,
+        );
+        let text = UiText::new(
+            font,
+            "Press any key to play".to_string(),
+            [1.0, 1.0, 1.0, 1.0],
+            28.0,
+        );
+        self.title = Some(world.create_entity().with(transform).with(text).build());
+    }
+
+    /// Takes the demo away and starts a game for the player.
+    fn stop_attract(&mut self, world: &mut World) {
+        self.attract = false;
+        *world.write_resource::<Controller>() = Controller::Player;
+        if let Some(title) = self.title.take() {
+            world.delete_entity(title).expect("Error deleting title");
+        }
+
+        new_game(world, GameRng::default(), 0);
+    }
+
+    /// Starts the game the launch options ask for, returning false if they don't ask for one.
+    fn start_launch_game(&mut self, world: &mut World) -> bool {
+        if let Some(replay) = self.options.replay.take() {
+            info!("Playing replay...");
+            world.insert(replay.config.clone());
+            world.write_resource::<Settings>().paddle_smoothing = replay.paddle_smoothing;
+            *world.write_resource::<Controller>() = Controller::External;
+            new_game(world, GameRng::from_seed(replay.seed), replay.level);
+            self.playback = Some(Playback { replay, next: 0 });
+            return true;
+        }
+
+        let options = &self.options;
+        if !self.headless
+            && options.level.is_none()
+            && options.seed.is_none()
+            && options.record.is_none()
+        {
+            return false;
+        }
+
+        if self.headless {
+            *world.write_resource::<Controller>() = Controller::Bot;
+        }
+        let rng = options
+            .seed
+            .map_or_else(GameRng::default, GameRng::from_seed);
+        new_game(world, rng, options.level.unwrap_or(0));
+        true
+    }
+
+    /// Sets this frame up the way it was when the replay was recorded.
+    fn play_frame(&mut self, world: &mut World) {
+        let playback = match self.playback.as_mut() {
+            Some(playback) => playback,
+            None => return,
+        };
+        let frame = match playback.replay.frames.get(playback.next) {
+            Some(frame) => frame,
+            None => return,
+        };
+        playback.next += 1;
+
+        world
+            .write_resource::<Time>()
+            .set_delta_seconds(frame.delta_seconds);
+        *world.write_resource::<PaddleInput>() = frame.input.clone();
+    }
+}
 
 impl SimpleState for GameState {
     fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
@@ -35,29 +300,321 @@ impl SimpleState for GameState {
 
         world.register::<Collidable>();
         world.register::<Ball>();
+        world.register::<Stuck>();
+        world.register::<Brick>();
+        world.register::<PowerUp>();
+        world.register::<Laser>();
+        world.register::<Tint>();
+        world.register::<DebugLinesComponent>();
 
-        let sprite_sheet_handle = load_sprite_sheet(world);
+        let sprite_sheet = load_sprite_sheet(world);
+        world.insert(GameSprites { sprite_sheet });
 
+        world.entry::<GameRng>().or_insert_with(GameRng::default);
         initialize_collision_context(world);
 
         initialize_camera(world);
-        initialize_paddle(world, sprite_sheet_handle.clone());
-        initialize_ball_destroyer(world);
-
-        let mut rand = thread_rng();
-        let dir = rand.gen_range(0.0, 2.0 * PI);
-        initialize_ball(
-            world,
-            sprite_sheet_handle,
-            Vector2::new(ARENA_WIDTH / 2.0, ARENA_HEIGHT / 2.0),
-            Vector2::new(
-                BALL_INITIAL_SPEED * dir.cos(),
-                BALL_INITIAL_SPEED * dir.sin(),
-            ),
+        world.entry::<Progress>().or_insert_with(Progress::default);
+
+        self.gameplay_reader = Some(
+            world
+                .fetch_mut::<EventChannel<GameplayEvent>>()
+                .register_reader(),
         );
+
+        if !self.start_launch_game(world) {
+            if SaveGame::resume(world) {
+                info!("Resumed saved game");
+            } else {
+                self.start_attract(world);
+            }
+        }
+
+        if let Some(path) = self.options.record.take() {
+            let replay = Replay {
+                seed: world.read_resource::<GameRng>().seed,
+                level: world.read_resource::<Progress>().level,
+                config: world.read_resource::<GameplayConfig>().clone(),
+                paddle_smoothing: world.read_resource::<Settings>().paddle_smoothing,
+                frames: vec![],
+            };
+            world.insert(Recording { path, replay });
+        }
+
+        if !self.headless {
+            apply_settings(world);
+        }
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        finish_recording(data.world);
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(event) {
+                if self.is_player_game() {
+                    save_game(data.world);
+                }
+                return Trans::Quit;
+            }
+
+            if self.attract {
+                if is_press(event) {
+                    self.stop_attract(data.world);
+                }
+                return Trans::None;
+            }
+
+            let options_key = data.world.read_resource::<Settings>().keybinds.options;
+            if self.playback.is_none() && is_key_down(event, options_key) {
+                return Trans::Push(Box::new(OptionsState::default()));
+            }
+        }
+
+        Trans::None
+    }
+
+    fn on_resume(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        if !self.game_over {
+            return;
+        }
+        self.game_over = false;
+
+        // back from the high scores, so back to the demo
+        self.start_attract(data.world);
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        if self.replay_finished() {
+            if self.headless {
+                return Trans::None;
+            }
+            info!("Replay finished");
+            return Trans::Quit;
+        }
+
+        let mut last_ball_lost = false;
+        let mut bricks_destroyed = 0;
+        {
+            let channel = data.world.read_resource::<EventChannel<GameplayEvent>>();
+            for event in channel.read(self.gameplay_reader.as_mut().unwrap()) {
+                match event {
+                    GameplayEvent::LastBallLost => last_ball_lost = true,
+                    GameplayEvent::BrickDestroyed { .. } => bricks_destroyed += 1,
+                    _ => {}
+                }
+            }
+        }
+
+        data.world.write_resource::<Progress>().score += bricks_destroyed * BRICK_POINTS;
+
+        if bricks_destroyed > 0 && level_cleared(data.world) {
+            next_level(data.world);
+            if self.is_player_game() {
+                save_game(data.world);
+            }
+        } else if last_ball_lost && !lose_life(data.world) {
+            finish_recording(data.world);
+
+            if let Some(playback) = self.playback.as_mut() {
+                // the replay has nothing more to show
+                playback.next = playback.replay.frames.len();
+                return Trans::None;
+            }
+
+            if self.attract || self.headless {
+                // the bot just goes again
+                let seed = data.world.write_resource::<GameRng>().rng.gen();
+                new_game(data.world, GameRng::from_seed(seed), 0);
+                return Trans::None;
+            }
+
+            info!("Game over!");
+            SaveGame::discard();
+
+            let score = {
+                let progress = data.world.read_resource::<Progress>();
+                let seed = data.world.read_resource::<GameRng>().seed;
+                HighScore::new(progress.score, progress.level + 1, seed)
+            };
+            clear_level(data.world);
+
+            self.game_over = true;
+            return Trans::Push(Box::new(HighScoreState::new(score)));
+        }
+
+        self.play_frame(data.world);
+        Trans::None
+    }
+}
+
+/// Writes out the recording, if there is one, and stops recording.
+fn finish_recording(world: &mut World) {
+    if let Some(recording) = world.remove::<Recording>() {
+        match recording.save() {
+            Ok(()) => info!("Recorded {:?}", recording.path),
+            Err(err) => error!("Error saving recording {:?}: {}", recording.path, err),
+        }
     }
 }
 
+/// Keeps the game in progress to pick back up next time.
+pub fn save_game(world: &World) {
+    if let Err(err) = SaveGame::capture(world).save() {
+        error!("Error saving game: {}", err);
+    }
+}
+
+/// Whether a key or mouse button was just pressed.
+fn is_press(event: &Event) -> bool {
+    match event {
+        Event::WindowEvent { event, .. } => match event {
+            WindowEvent::KeyboardInput { input, .. } => input.state == ElementState::Pressed,
+            WindowEvent::MouseInput { state, .. } => *state == ElementState::Pressed,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Throws away whatever is in play and starts over from the given level.
+fn new_game(world: &mut World, rng: GameRng, level: usize) {
+    clear_level(world);
+    world.insert(Progress {
+        level,
+        ..Progress::default()
+    });
+    world.insert(BotRng::from_seed(rng.seed));
+    world.insert(rng);
+    start_level(world);
+}
+
+/// Builds the current level of the campaign, starting from a collision world holding just its
+/// arena, and serves the first ball.
+fn start_level(world: &mut World) {
+    let index = world.read_resource::<Progress>().level;
+    let level = world.read_resource::<Campaign>().level(index);
+    info!("Starting level {}...", index + 1);
+
+    let sprite_sheet_handle = world.read_resource::<GameSprites>().sprite_sheet.clone();
+    build_arena(world, &level.arena);
+    initialize_paddle(
+        world,
+        sprite_sheet_handle.clone(),
+        ARENA_WIDTH / 2.0,
+        PADDLE_WIDTH,
+    );
+    initialize_bricks(world, sprite_sheet_handle, &level);
+
+    world.insert(SpeedProgress {
+        level: index,
+        ..SpeedProgress::default()
+    });
+
+    serve_ball(world);
+}
+
+/// Starts the collision world over with just the arena's walls, and adds everything else that
+/// comes with the arena.
+pub fn build_arena(world: &mut World, arena: &Arena) {
+    {
+        let mut context = world.write_resource::<CollisionContext>();
+        context.arena = arena.clone();
+        context.reset();
+    }
+
+    initialize_arena(world, arena);
+    initialize_ball_destroyers(world, arena);
+}
+
+/// Removes everything the current level put in the world, leaving the camera and resources.
+pub fn clear_level(world: &mut World) {
+    let level_entities: Vec<Entity> = {
+        let entities = world.entities();
+        let collidables = world.read_storage::<Collidable>();
+        let lines = world.read_storage::<DebugLinesComponent>();
+
+        (&entities, collidables.mask() | lines.mask())
+            .join()
+            .map(|(entity, _)| entity)
+            .collect()
+    };
+    world
+        .delete_entities(&level_entities)
+        .expect("Error clearing level");
+    world.maintain();
+
+    world.insert(ActiveContacts::default());
+    world.insert(ActivePowerUps::default());
+}
+
+fn level_cleared(world: &World) -> bool {
+    !world
+        .read_storage::<Brick>()
+        .join()
+        .any(|brick| brick.destructible)
+}
+
+/// Awards the level's bonus lives and moves on to the next level, going back to the first one
+/// once the campaign is complete.
+fn next_level(world: &mut World) {
+    {
+        let campaign = world.read_resource::<Campaign>();
+        let mut progress = world.write_resource::<Progress>();
+
+        let bonus = campaign.lives_bonus(progress.level);
+        progress.lives += bonus;
+        progress.level += 1;
+        if progress.level >= campaign.levels.len() {
+            info!("Campaign complete!");
+            progress.level = 0;
+        }
+    }
+
+    clear_level(world);
+    start_level(world);
+}
+
+/// Serves another ball if there are lives left, returning false once the game is over.
+fn lose_life(world: &mut World) -> bool {
+    {
+        let mut progress = world.write_resource::<Progress>();
+        if progress.lives == 0 {
+            return false;
+        }
+
+        progress.lives -= 1;
+        info!("{} lives left", progress.lives);
+    }
+
+    serve_ball(world);
+    true
+}
+
+/// Puts a new ball in the middle of the arena, heading in a random direction.
+fn serve_ball(world: &mut World) {
+    let sprite_sheet_handle = world.read_resource::<GameSprites>().sprite_sheet.clone();
+
+    let dir = world
+        .write_resource::<GameRng>()
+        .rng
+        .gen_range(0.0, 2.0 * PI);
+    initialize_ball(
+        world,
+        sprite_sheet_handle,
+        Vector2::new(ARENA_WIDTH / 2.0, ARENA_HEIGHT / 2.0),
+        Vector2::new(
+            BALL_INITIAL_SPEED * dir.cos(),
+            BALL_INITIAL_SPEED * dir.sin(),
+        ),
+    );
+}
+
 fn load_sprite_sheet(world: &mut World) -> Handle<SpriteSheet> {
     let texture_handle = {
         let loader = world.read_resource::<Loader>();
@@ -80,33 +637,39 @@ fn load_sprite_sheet(world: &mut World) -> Handle<SpriteSheet> {
     )
 }
 
-fn initialize_paddle(world: &mut World, sprite_sheet_handle: Handle<SpriteSheet>) {
+pub fn initialize_paddle(
+    world: &mut World,
+    sprite_sheet_handle: Handle<SpriteSheet>,
+    x: f32,
+    width: f32,
+) {
     let mut transform = Transform::default();
-    transform.set_translation_xyz(ARENA_WIDTH / 2.0, PADDLE_HEIGHT / 2.0, 0.0);
+    transform.set_translation_xyz(x, PADDLE_HEIGHT / 2.0, 0.0);
+    transform.set_scale(Vector3::new(width / PADDLE_WIDTH, 1.0, 1.0));
 
     let render = SpriteRender {
         sprite_sheet: sprite_sheet_handle,
         sprite_number: 0,
     };
 
-    let shape = Cuboid::new(Vector2::new(PADDLE_WIDTH / 2.0, PADDLE_HEIGHT / 2.0));
-    let collidable = Collidable::new_other(world, &transform, shape);
+    let shape = Cuboid::new(Vector2::new(width / 2.0, PADDLE_HEIGHT / 2.0));
+    let builder = world.create_entity();
+    let collidable = Collidable::new_other(builder.world, builder.entity, &transform, shape);
 
-    world
-        .create_entity()
-        .with(Paddle)
+    builder
+        .with(Paddle::new(width))
         .with(collidable)
         .with(transform)
         .with(render)
         .build();
 }
 
-fn initialize_ball(
+pub fn initialize_ball(
     world: &mut World,
     sprite_sheet_handle: Handle<SpriteSheet>,
     position: Vector2<f32>,
     velocity: Vector2<f32>,
-) {
+) -> Entity {
     let mut transform = Transform::default();
     transform.set_translation_xyz(position.x, position.y, 0.0);
 
@@ -117,15 +680,160 @@ fn initialize_ball(
         sprite_number: 1,
     };
 
-    let shape = Cuboid::new(Vector2::new(BALL_WIDTH / 2.0, BALL_HEIGHT / 2.0));
-    let collidable = Collidable::new_ball(world, &transform, shape);
+    let shape = BallShape::new(BALL_RADIUS);
+    let builder = world.create_entity();
+    let collidable = Collidable::new_ball(builder.world, builder.entity, &transform, shape);
 
-    world
-        .create_entity()
+    builder
         .with(ball)
         .with(collidable)
         .with(transform)
         .with(render)
+        .build()
+}
+
+fn initialize_bricks(world: &mut World, sprite_sheet_handle: Handle<SpriteSheet>, level: &Level) {
+    let columns = level
+        .bricks
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+    let grid_width = columns as f32 * (BRICK_WIDTH + BRICK_SPACING) - BRICK_SPACING;
+    let left = (ARENA_WIDTH - grid_width) / 2.0 + BRICK_WIDTH / 2.0;
+    let top = ARENA_HEIGHT - BRICK_TOP_MARGIN - BRICK_HEIGHT / 2.0;
+
+    for (row, line) in level.bricks.iter().enumerate() {
+        for (column, c) in line.chars().enumerate() {
+            let (sprite_number, destructible) = match c.to_digit(10) {
+                Some(colour) if colour > 0 => (1 + colour as usize, true),
+                _ if c == '#' => (10, false),
+                _ => continue,
+            };
+
+            let position = Vector2::new(
+                left + column as f32 * (BRICK_WIDTH + BRICK_SPACING),
+                top - row as f32 * (BRICK_HEIGHT + BRICK_SPACING),
+            );
+            let follower = level.motion(row).map(|motion| {
+                PathFollower::new(motion.path.clone(), motion.spin.to_radians(), position)
+            });
+
+            initialize_brick(
+                world,
+                sprite_sheet_handle.clone(),
+                position,
+                Brick { row, destructible },
+                sprite_number,
+                follower,
+            );
+        }
+    }
+}
+
+pub fn initialize_brick(
+    world: &mut World,
+    sprite_sheet_handle: Handle<SpriteSheet>,
+    position: Vector2<f32>,
+    brick: Brick,
+    sprite_number: usize,
+    follower: Option<PathFollower>,
+) {
+    let mut transform = Transform::default();
+    transform.set_translation_xyz(position.x, position.y, 0.0);
+    if let Some(follower) = follower.as_ref() {
+        transform.set_rotation_2d(follower.spin * follower.elapsed);
+    }
+
+    let render = SpriteRender {
+        sprite_sheet: sprite_sheet_handle,
+        sprite_number,
+    };
+
+    let shape = Cuboid::new(Vector2::new(BRICK_WIDTH / 2.0, BRICK_HEIGHT / 2.0));
+    let builder = world.create_entity();
+    let collidable = Collidable::new_other(builder.world, builder.entity, &transform, shape);
+
+    let destructible = brick.destructible;
+    let mut builder = builder
+        .with(brick)
+        .with(collidable)
+        .with(transform)
+        .with(render);
+    let tint = if destructible {
+        let palette = builder.world.read_resource::<Settings>().palette;
+        palette.brick_tint(sprite_number - 1)
+    } else {
+        // indestructible bricks are greyed out
+        Some(Tint(Srgba::new(0.4, 0.4, 0.4, 1.0)))
+    };
+    if let Some(tint) = tint {
+        builder = builder.with(tint);
+    }
+    if let Some(follower) = follower {
+        builder = builder.with(follower);
+    }
+    builder.build();
+}
+
+pub fn initialize_power_up(
+    world: &mut World,
+    sprite_sheet_handle: Handle<SpriteSheet>,
+    position: Vector2<f32>,
+    kind: PowerUpKind,
+) {
+    let mut transform = Transform::default();
+    transform.set_translation_xyz(position.x, position.y, 0.0);
+    transform.set_scale(Vector3::new(
+        POWER_UP_WIDTH / BRICK_WIDTH,
+        POWER_UP_HEIGHT / BRICK_HEIGHT,
+        1.0,
+    ));
+
+    let render = SpriteRender {
+        sprite_sheet: sprite_sheet_handle,
+        sprite_number: kind.sprite_number(),
+    };
+
+    let shape = Cuboid::new(Vector2::new(POWER_UP_WIDTH / 2.0, POWER_UP_HEIGHT / 2.0));
+    let builder = world.create_entity();
+    let collidable = Collidable::new_power_up(builder.world, builder.entity, &transform, shape);
+
+    builder
+        .with(PowerUp { kind })
+        .with(collidable)
+        .with(transform)
+        .with(render)
+        .build();
+}
+
+pub fn initialize_laser(
+    world: &mut World,
+    sprite_sheet_handle: Handle<SpriteSheet>,
+    position: Vector2<f32>,
+) {
+    let mut transform = Transform::default();
+    transform.set_translation_xyz(position.x, position.y, 0.0);
+    transform.set_scale(Vector3::new(
+        LASER_WIDTH / BALL_WIDTH,
+        LASER_HEIGHT / BALL_HEIGHT,
+        1.0,
+    ));
+
+    let render = SpriteRender {
+        sprite_sheet: sprite_sheet_handle,
+        sprite_number: 1,
+    };
+
+    let shape = Cuboid::new(Vector2::new(LASER_WIDTH / 2.0, LASER_HEIGHT / 2.0));
+    let builder = world.create_entity();
+    let collidable = Collidable::new_laser(builder.world, builder.entity, &transform, shape);
+
+    builder
+        .with(Laser)
+        .with(collidable)
+        .with(transform)
+        .with(render)
         .build();
 }
 
@@ -140,17 +848,87 @@ fn initialize_camera(world: &mut World) {
         .build();
 }
 
-fn initialize_ball_destroyer(world: &mut World) {
-    let mut transform = Transform::default();
-    transform.set_translation_xyz(ARENA_WIDTH / 2.0, 0.0, 0.0);
+/// Puts a `BallDestroyer` along every lethal edge of the arena.
+fn initialize_ball_destroyers(world: &mut World, arena: &Arena) {
+    for &(_, edge, normal, position) in arena.edges().iter() {
+        if edge != Edge::Lethal {
+            continue;
+        }
 
-    let shape = Plane::new(Vector2::y_axis());
-    let collidable = Collidable::new_other(world, &transform, shape);
+        let mut transform = Transform::default();
+        transform.set_translation_xyz(position.x, position.y, 0.0);
 
-    world
-        .create_entity()
-        .with(BallDestroyer)
-        .with(collidable)
-        .with(transform)
-        .build();
+        let shape = Plane::new(normal);
+        let builder = world.create_entity();
+        let collidable = Collidable::new_other(builder.world, builder.entity, &transform, shape);
+
+        builder
+            .with(BallDestroyer)
+            .with(collidable)
+            .with(transform)
+            .build();
+    }
+}
+
+/// Draws the arena's walls, lethal edges and portals.
+fn initialize_arena(world: &mut World, arena: &Arena) {
+    let wall_colour = Srgba::new(0.8, 0.8, 0.8, 1.0);
+    let lethal_colour = Srgba::new(0.8, 0.1, 0.1, 1.0);
+    let entrance_colour = Srgba::new(0.2, 0.4, 1.0, 1.0);
+    let exit_colour = Srgba::new(1.0, 0.6, 0.1, 1.0);
+
+    let mut lines = DebugLinesComponent::new();
+
+    for wall in arena.walls.iter() {
+        let points = wall.points();
+        let closed = matches!(wall, Wall::Polygon { .. });
+
+        for (i, from) in points.iter().enumerate() {
+            let to = match points.get(i + 1) {
+                Some(to) => to,
+                None if closed && points.len() > 2 => &points[0],
+                None => break,
+            };
+
+            lines.add_line(
+                Point3::new(from.x, from.y, 0.0),
+                Point3::new(to.x, to.y, 0.0),
+                wall_colour,
+            );
+        }
+    }
+
+    for &(_, edge, normal, position) in arena.edges().iter() {
+        if edge == Edge::Lethal {
+            // the edge runs perpendicular to its normal, across the whole arena
+            let along = Vector2::new(-normal.y, normal.x) * arena.width.max(arena.height);
+            let from = position - along;
+            let to = position + along;
+
+            lines.add_line(
+                Point3::new(from.x, from.y, 0.0),
+                Point3::new(to.x, to.y, 0.0),
+                lethal_colour,
+            );
+        }
+    }
+
+    for portal in arena.portals.iter() {
+        let (entrance, exit) = (portal.entrance, portal.exit);
+
+        lines.add_circle_2d(
+            Point3::new(entrance.0, entrance.1, 0.0),
+            portal.radius,
+            PORTAL_SEGMENTS,
+            entrance_colour,
+        );
+        lines.add_circle_2d(
+            Point3::new(exit.0, exit.1, 0.0),
+            portal.radius,
+            PORTAL_SEGMENTS,
+            exit_colour,
+        );
+    }
+
+    world.create_entity().with(lines).build();
 }
@@ -0,0 +1,324 @@
+use crate::{
+    components::{Ball, Brick, Stuck},
+    config::GameplayConfig,
+    events::GameplayEvent,
+    game::{
+        initialize_ball, GameRng, GameSprites, GameState, LaunchOptions, ARENA_HEIGHT, ARENA_WIDTH,
+        BALL_INITIAL_SPEED, BALL_WIDTH, HEADLESS_STEP,
+    },
+    level::{Campaign, Progress},
+    systems::SystemTimings,
+};
+use amethyst::{
+    assets::{AssetStorage, Loader},
+    core::{math::Vector2, timing::Time, transform::Transform, ArcThreadPool},
+    ecs::{Entity, Join},
+    prelude::{GameDataBuilder, World, WorldExt},
+    renderer::{Camera, SpriteRender, SpriteSheet, Texture},
+    shrev::{EventChannel, ReaderId},
+    DataInit, GameData, SimpleState, StateData,
+};
+use log::warn;
+use rand::Rng;
+use rayon::ThreadPoolBuilder;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    f32::consts::PI,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// The game without a window, advanced a fixed step at a time as fast as it will go, with the
+/// bot playing unless a replay is being played.
+pub struct Simulation {
+    pub world: World,
+    game_data: GameData<'static, 'static>,
+    state: GameState,
+}
+
+/// Where a game has got to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    pub level: usize,
+    pub lives: u32,
+    pub score: u32,
+    /// Destructible bricks left.
+    pub bricks: usize,
+    /// Where each ball in play is, rounded to the nearest unit, from left to right.
+    pub balls: Vec<(i32, i32)>,
+}
+
+impl Simulation {
+    /// Sets up a world for the gameplay systems in `game_data`, which shouldn't include any
+    /// rendering, and starts the first game.
+    pub fn new(
+        assets_dir: &Path,
+        game_data: GameDataBuilder<'static, 'static>,
+        gameplay_config: GameplayConfig,
+        campaign: Campaign,
+        options: LaunchOptions,
+    ) -> amethyst::Result<Simulation> {
+        let mut world = World::new();
+
+        // what the application and the rendering bundle would otherwise provide
+        let pool: ArcThreadPool = Arc::new(ThreadPoolBuilder::new().build()?);
+        world.insert(Loader::new(assets_dir, pool.clone()));
+        world.insert(pool);
+        world.insert(Time::default());
+        world.insert(AssetStorage::<Texture>::default());
+        world.insert(AssetStorage::<SpriteSheet>::default());
+        world.register::<SpriteRender>();
+        world.register::<Camera>();
+
+        world.insert(gameplay_config);
+        world.insert(campaign);
+
+        let mut game_data = game_data.build(&mut world);
+        let mut state = GameState::headless(options);
+        state.on_start(StateData::new(&mut world, &mut game_data));
+
+        Ok(Simulation {
+            world,
+            game_data,
+            state,
+        })
+    }
+
+    /// Starts a new game from the given seed.
+    pub fn reset(&mut self, seed: u64) {
+        self.state
+            .restart(&mut self.world, GameRng::from_seed(seed));
+        self.world.maintain();
+    }
+
+    /// Advances the game by `HEADLESS_STEP` seconds.
+    pub fn step(&mut self) {
+        self.world
+            .write_resource::<Time>()
+            .set_delta_seconds(HEADLESS_STEP);
+
+        self.state
+            .update(&mut StateData::new(&mut self.world, &mut self.game_data));
+        self.game_data.update(&self.world);
+        self.world.maintain();
+    }
+
+    /// Whether the replay being played has come to an end.
+    pub fn finished(&self) -> bool {
+        self.state.replay_finished()
+    }
+
+    /// Stops the game as if the window had been closed, writing out any recording.
+    pub fn stop(&mut self) {
+        self.state
+            .on_stop(StateData::new(&mut self.world, &mut self.game_data));
+    }
+
+    pub fn summary(&self) -> Summary {
+        let world = &self.world;
+        let progress = world.read_resource::<Progress>();
+        let transforms = world.read_storage::<Transform>();
+
+        let bricks = world
+            .read_storage::<Brick>()
+            .join()
+            .filter(|brick| brick.destructible)
+            .count();
+        let mut balls: Vec<(i32, i32)> = (&world.read_storage::<Ball>(), &transforms)
+            .join()
+            .map(|(_, transform)| {
+                let translation = transform.translation();
+                (translation.x.round() as i32, translation.y.round() as i32)
+            })
+            .collect();
+        balls.sort();
+
+        Summary {
+            level: progress.level,
+            lives: progress.lives,
+            score: progress.score,
+            bricks,
+            balls,
+        }
+    }
+}
+
+/// How a soak test went.
+#[derive(Debug, Default)]
+pub struct SoakReport {
+    pub seed: u64,
+    pub ticks: u64,
+    pub balls_lost: usize,
+    pub bricks_destroyed: usize,
+    pub levels_cleared: usize,
+    /// Balls found well outside of the arena.
+    pub escaped_balls: usize,
+    /// Balls found with a velocity that isn't a finite number.
+    pub invalid_velocities: usize,
+    /// Whether the test was cut short because nothing had happened for too long.
+    pub stalled: bool,
+}
+
+impl SoakReport {
+    pub fn passed(&self) -> bool {
+        self.escaped_balls == 0 && self.invalid_velocities == 0 && !self.stalled
+    }
+}
+
+/// Seconds of play without a ball being lost or a brick destroyed before a soak test gives up.
+const SOAK_STALL_SECONDS: f32 = 600.0;
+
+/// Lets the bot play until `balls` balls have been lost, checking every step that each ball is
+/// still somewhere sensible.
+pub fn run_soak(simulation: &mut Simulation, balls: usize) -> SoakReport {
+    let mut reader: ReaderId<GameplayEvent> = simulation
+        .world
+        .fetch_mut::<EventChannel<GameplayEvent>>()
+        .register_reader();
+    let mut report = SoakReport {
+        seed: simulation.world.read_resource::<GameRng>().seed,
+        ..SoakReport::default()
+    };
+    let mut level = simulation.world.read_resource::<Progress>().level;
+    let mut quiet_ticks = 0;
+    let mut escaped: HashSet<Entity> = HashSet::new();
+    let mut invalid: HashSet<Entity> = HashSet::new();
+
+    while report.balls_lost < balls {
+        simulation.step();
+        report.ticks += 1;
+        quiet_ticks += 1;
+
+        let world = &simulation.world;
+        for event in world
+            .read_resource::<EventChannel<GameplayEvent>>()
+            .read(&mut reader)
+        {
+            match event {
+                GameplayEvent::BallLost { .. } => report.balls_lost += 1,
+                GameplayEvent::BrickDestroyed { .. } => report.bricks_destroyed += 1,
+                _ => continue,
+            }
+            quiet_ticks = 0;
+        }
+
+        let progress = world.read_resource::<Progress>();
+        if progress.level != level {
+            // a new game starts from the first level with no score
+            if progress.score > 0 {
+                report.levels_cleared += 1;
+            }
+            level = progress.level;
+        }
+
+        let margin = BALL_WIDTH * 2.0;
+        for (entity, ball, transform, _) in (
+            &world.entities(),
+            &world.read_storage::<Ball>(),
+            &world.read_storage::<Transform>(),
+            !&world.read_storage::<Stuck>(),
+        )
+            .join()
+        {
+            let translation = transform.translation();
+            let outside = translation.x < -margin
+                || translation.x > ARENA_WIDTH + margin
+                || translation.y < -margin
+                || translation.y > ARENA_HEIGHT + margin;
+            if outside && escaped.insert(entity) {
+                warn!("Ball escaped the arena at {:?}", translation);
+            }
+            if !ball.velocity.iter().all(|v| v.is_finite()) && invalid.insert(entity) {
+                warn!("Ball has an invalid velocity {:?}", ball.velocity);
+            }
+        }
+
+        if quiet_ticks as f32 * HEADLESS_STEP > SOAK_STALL_SECONDS {
+            warn!("Nothing has happened for {} seconds", SOAK_STALL_SECONDS);
+            report.stalled = true;
+            break;
+        }
+    }
+
+    report.escaped_balls = escaped.len();
+    report.invalid_velocities = invalid.len();
+    report
+}
+
+/// How a bench run went.
+#[derive(Debug, Default)]
+pub struct BenchReport {
+    pub ticks: u64,
+    pub balls: usize,
+    /// Bricks in play at the end, destructible or not.
+    pub bricks: usize,
+    pub elapsed: Duration,
+    pub world_update: Duration,
+    pub ball_collision: Duration,
+}
+
+impl BenchReport {
+    pub fn ticks_per_second(&self) -> f64 {
+        self.ticks as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+/// Steps the game `ticks` times as fast as it will go, keeping `balls` balls in play, and times
+/// the collision systems. Meant for a stress level like `config/levels/stress.ron`, with a wall
+/// along the bottom so that balls aren't lost.
+pub fn run_bench(simulation: &mut Simulation, ticks: u64, balls: usize) -> BenchReport {
+    simulation.world.read_resource::<SystemTimings>().reset();
+
+    let mut elapsed = Duration::default();
+    for _ in 0..ticks {
+        // a cleared level comes back with just the one ball
+        top_up_balls(&mut simulation.world, balls);
+        simulation.world.maintain();
+
+        let start = Instant::now();
+        simulation.step();
+        elapsed += start.elapsed();
+    }
+
+    let world = &simulation.world;
+    let timings = world.read_resource::<SystemTimings>();
+    BenchReport {
+        ticks,
+        balls: world.read_storage::<Ball>().join().count(),
+        bricks: world.read_storage::<Brick>().join().count(),
+        elapsed,
+        world_update: timings.get("world_update_system"),
+        ball_collision: timings.get("ball_collision_system"),
+    }
+}
+
+/// Serves balls below the bricks, heading every which way, until there are `balls` of them.
+fn top_up_balls(world: &mut World, balls: usize) {
+    let count = world.read_storage::<Ball>().join().count();
+    if count >= balls {
+        return;
+    }
+
+    let sprite_sheet_handle = world.read_resource::<GameSprites>().sprite_sheet.clone();
+    for _ in count..balls {
+        let (x, y, dir) = {
+            let rng = &mut world.write_resource::<GameRng>().rng;
+            (
+                rng.gen_range(BALL_WIDTH, ARENA_WIDTH - BALL_WIDTH),
+                rng.gen_range(ARENA_HEIGHT / 3.0, ARENA_HEIGHT / 2.0),
+                rng.gen_range(0.0, 2.0 * PI),
+            )
+        };
+        initialize_ball(
+            world,
+            sprite_sheet_handle.clone(),
+            Vector2::new(x, y),
+            Vector2::new(
+                BALL_INITIAL_SPEED * dir.cos(),
+                BALL_INITIAL_SPEED * dir.sin(),
+            ),
+        );
+    }
+}
@@ -0,0 +1,249 @@
+use crate::{
+    game::{HIGH_SCORE_COUNT, HIGH_SCORE_NAME_LENGTH},
+    save::{user_data_path, write_user_data},
+};
+use amethyst::{
+    assets::Loader,
+    config::{Config, ConfigError},
+    ecs::Entity,
+    input::{is_close_requested, is_key_down, VirtualKeyCode},
+    prelude::{Builder, SimpleTrans, StateEvent, Trans, World, WorldExt},
+    ui::{Anchor, FontHandle, TtfFormat, UiText, UiTransform},
+    winit::{Event, WindowEvent},
+    GameData, SimpleState, StateData,
+};
+use log::error;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HighScore {
+    pub name: String,
+    pub score: u32,
+    /// The level the game ended on, counting from one.
+    pub level: usize,
+    pub date: String,
+    pub seed: u64,
+}
+
+impl HighScore {
+    /// A score set today, still waiting for the player to enter their name.
+    pub fn new(score: u32, level: usize, seed: u64) -> HighScore {
+        HighScore {
+            name: String::new(),
+            score,
+            level,
+            date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+            seed,
+        }
+    }
+}
+
+/// The best scores so far, highest first, kept in the user's data directory.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct HighScores {
+    pub entries: Vec<HighScore>,
+}
+
+const HIGH_SCORES_FILE: &str = "high_scores.ron";
+
+impl HighScores {
+    /// Loads the table, starting a new one if there isn't one yet or it can't be read.
+    pub fn load_or_default() -> HighScores {
+        match user_data_path(HIGH_SCORES_FILE) {
+            Some(path) if path.exists() => HighScores::load(&path).unwrap_or_else(|err| {
+                error!("Error loading high scores from {:?}: {}", path, err);
+                HighScores::default()
+            }),
+            _ => HighScores::default(),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), ConfigError> {
+        write_user_data(self, HIGH_SCORES_FILE)
+    }
+
+    /// Whether the score is good enough to make it onto the table.
+    pub fn qualifies(&self, score: u32) -> bool {
+        score > 0
+            && (self.entries.len() < HIGH_SCORE_COUNT
+                || self
+                    .entries
+                    .last()
+                    .map_or(true, |lowest| score > lowest.score))
+    }
+
+    pub fn insert(&mut self, entry: HighScore) {
+        let position = self
+            .entries
+            .iter()
+            .position(|other| entry.score > other.score)
+            .unwrap_or(self.entries.len());
+
+        self.entries.insert(position, entry);
+        self.entries.truncate(HIGH_SCORE_COUNT);
+    }
+}
+
+/// Shown after game over. Has the player enter their name if they made it onto the table, then
+/// shows the table until they press enter or escape.
+pub struct HighScoreState {
+    score: Option<HighScore>,
+    font: Option<FontHandle>,
+    prompt: Option<Entity>,
+    ui: Vec<Entity>,
+}
+
+impl HighScoreState {
+    pub fn new(score: HighScore) -> HighScoreState {
+        HighScoreState {
+            score: Some(score),
+            font: None,
+            prompt: None,
+            ui: vec![],
+        }
+    }
+
+    fn add_line(&mut self, world: &mut World, text: String, line: usize) -> Entity {
+        let transform = UiTransform::new(
+            format!("high_score_{}", line),
+            Anchor::TopMiddle,
+            Anchor::TopMiddle,
+            0.0,
+            -80.0 - line as f32 * 40.0,
+            1.0,
+            960.0,
+            40.0,
+        );
+        let text = UiText::new(self.font.clone().unwrap(), text, [1.0, 1.0, 1.0, 1.0], 28.0);
+
+        let entity = world.create_entity().with(transform).with(text).build();
+        self.ui.push(entity);
+        entity
+    }
+
+    fn show_table(&mut self, world: &mut World) {
+        if let Some(prompt) = self.prompt.take() {
+            world.delete_entity(prompt).expect("Error deleting prompt");
+            self.ui.retain(|&entity| entity != prompt);
+        }
+
+        let lines: Vec<String> = world
+            .read_resource::<HighScores>()
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                format!(
+                    "{:>2}. {:<12} {:>7}  level {:<2}  {}",
+                    i + 1,
+                    entry.name,
+                    entry.score,
+                    entry.level,
+                    entry.date
+                )
+            })
+            .collect();
+
+        for (i, line) in lines.into_iter().enumerate() {
+            self.add_line(world, line, i + 2);
+        }
+    }
+
+    fn update_prompt(&self, world: &mut World) {
+        if let (Some(prompt), Some(score)) = (self.prompt, self.score.as_ref()) {
+            if let Some(text) = world.write_storage::<UiText>().get_mut(prompt) {
+                text.text = prompt_text(&score.name);
+            }
+        }
+    }
+}
+
+impl SimpleState for HighScoreState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+
+        self.font = Some(world.read_resource::<Loader>().load(
+            "font/square.ttf",
+            TtfFormat,
+            (),
+            &world.read_resource(),
+        ));
+
+        let qualifies = self.score.as_ref().map_or(false, |score| {
+            world.read_resource::<HighScores>().qualifies(score.score)
+        });
+        if !qualifies {
+            self.score = None;
+        }
+
+        self.add_line(world, "GAME OVER".to_string(), 0);
+        if self.score.is_some() {
+            self.prompt = Some(self.add_line(world, prompt_text(""), 1));
+        } else {
+            self.show_table(world);
+        }
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        data.world
+            .delete_entities(&self.ui)
+            .expect("Error deleting high scores");
+        self.ui.clear();
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        let event = match event {
+            StateEvent::Window(event) => event,
+            _ => return Trans::None,
+        };
+        if is_close_requested(&event) {
+            return Trans::Quit;
+        }
+
+        let submit = is_key_down(&event, VirtualKeyCode::Return);
+
+        let name = match self.score.as_mut() {
+            Some(score) => &mut score.name,
+            None => {
+                if submit || is_key_down(&event, VirtualKeyCode::Escape) {
+                    return Trans::Pop;
+                }
+                return Trans::None;
+            }
+        };
+
+        if submit && !name.is_empty() {
+            let score = self.score.take().unwrap();
+            {
+                let mut high_scores = data.world.write_resource::<HighScores>();
+                high_scores.insert(score);
+                if let Err(err) = high_scores.save() {
+                    error!("Error saving high scores: {}", err);
+                }
+            }
+            self.show_table(data.world);
+        } else if is_key_down(&event, VirtualKeyCode::Back) {
+            name.pop();
+            self.update_prompt(data.world);
+        } else if let Event::WindowEvent {
+            event: WindowEvent::ReceivedCharacter(c),
+            ..
+        } = event
+        {
+            if !c.is_control() && name.chars().count() < HIGH_SCORE_NAME_LENGTH {
+                name.push(c);
+                self.update_prompt(data.world);
+            }
+        }
+
+        Trans::None
+    }
+}
+
+fn prompt_text(name: &str) -> String {
+    format!("New high score! Enter your name: {}_", name)
+}
@@ -0,0 +1,362 @@
+use crate::game::{ARENA_HEIGHT, ARENA_WIDTH, BRICK_COLUMNS, BRICK_ROWS, STARTING_LIVES};
+use amethyst::{
+    config::{Config, ConfigError},
+    core::math::{Point2, Unit, Vector2},
+};
+use ncollide2d::shape::{ConvexPolygon, Segment, ShapeHandle};
+use serde::{Deserialize, Serialize};
+use std::{f32::consts::PI, io, path::Path};
+
+/// The levels to play through, in order, loaded from `config/campaign.ron`.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Campaign {
+    pub levels: Vec<CampaignLevel>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CampaignLevel {
+    /// The level file, relative to the campaign file.
+    pub file: String,
+    /// A fixed ball speed for this level, overriding the gameplay speed policy.
+    #[serde(default)]
+    pub ball_speed: Option<f32>,
+    /// Extra lives awarded for clearing this level.
+    #[serde(default)]
+    pub lives_bonus: u32,
+    #[serde(skip)]
+    pub level: Level,
+}
+
+impl Campaign {
+    /// Loads the campaign file along with every level it lists.
+    pub fn load_with_levels<P: AsRef<Path>>(path: P) -> Result<Campaign, ConfigError> {
+        let path = path.as_ref();
+        let mut campaign = Campaign::load(path)?;
+
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        for entry in campaign.levels.iter_mut() {
+            let level_path = dir.join(&entry.file);
+            entry.level = Level::load(&level_path)?;
+            entry.level.check().map_err(|message| {
+                ConfigError::File(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{:?}: {}", level_path, message),
+                ))
+            })?;
+        }
+
+        Ok(campaign)
+    }
+
+    /// The level to build for the given index, falling back to the default layout when the
+    /// campaign is empty.
+    pub fn level(&self, index: usize) -> Level {
+        self.levels
+            .get(index)
+            .map(|entry| entry.level.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn ball_speed(&self, index: usize) -> Option<f32> {
+        self.levels.get(index).and_then(|entry| entry.ball_speed)
+    }
+
+    pub fn lives_bonus(&self, index: usize) -> u32 {
+        self.levels.get(index).map_or(0, |entry| entry.lives_bonus)
+    }
+}
+
+/// A brick layout, one string per row from the top. The digits `1` to `9` are bricks of that
+/// colour, `#` is an indestructible brick and anything else is a gap.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Level {
+    pub bricks: Vec<String>,
+    #[serde(default)]
+    pub arena: Arena,
+    #[serde(default)]
+    pub motions: Vec<RowMotion>,
+}
+
+impl Default for Level {
+    /// A full grid with a differently coloured row of bricks for every row.
+    fn default() -> Level {
+        let bricks = (0..BRICK_ROWS)
+            .map(|row| {
+                std::iter::repeat(std::char::from_digit((row % 9 + 1) as u32, 10).unwrap())
+                    .take(BRICK_COLUMNS)
+                    .collect()
+            })
+            .collect();
+
+        Level {
+            bricks,
+            arena: Arena::default(),
+            motions: vec![],
+        }
+    }
+}
+
+/// Moves every brick in the given rows along a path, optionally spinning them as they go.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RowMotion {
+    pub rows: Vec<usize>,
+    pub path: Path,
+    /// How fast the bricks spin, in degrees per second.
+    #[serde(default)]
+    pub spin: f32,
+}
+
+impl Level {
+    /// Checks that every wall in the arena can be built.
+    pub fn check(&self) -> Result<(), String> {
+        self.arena.walls.iter().try_for_each(Wall::check)
+    }
+
+    pub fn motion(&self, row: usize) -> Option<&RowMotion> {
+        self.motions
+            .iter()
+            .find(|motion| motion.rows.contains(&row))
+    }
+}
+
+/// How something moves relative to where it started.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Path {
+    /// Travels at a constant speed through each of the points, given as offsets from the start,
+    /// then back to the start and around again.
+    Waypoints { points: Vec<(f32, f32)>, speed: f32 },
+    /// Swings back and forth around the start, taking `period` seconds per swing.
+    Sine { amplitude: (f32, f32), period: f32 },
+}
+
+impl Path {
+    /// How far from the start the path has got after `elapsed` seconds.
+    pub fn offset(&self, elapsed: f32) -> Vector2<f32> {
+        match self {
+            Path::Waypoints { points, speed } => {
+                let mut corners = vec![Vector2::zeros()];
+                corners.extend(points.iter().map(|&(x, y)| Vector2::new(x, y)));
+
+                let segments: Vec<_> = (0..corners.len())
+                    .map(|i| (corners[i], corners[(i + 1) % corners.len()]))
+                    .collect();
+                let total: f32 = segments.iter().map(|(from, to)| (to - from).norm()).sum();
+                if total <= 0.0 {
+                    return Vector2::zeros();
+                }
+
+                let mut distance = (elapsed * speed).rem_euclid(total);
+                for (from, to) in segments {
+                    let length = (to - from).norm();
+                    if length > 0.0 && distance <= length {
+                        return from + (to - from) * (distance / length);
+                    }
+                    distance -= length;
+                }
+
+                Vector2::zeros()
+            }
+            Path::Sine { amplitude, period } => {
+                if *period <= 0.0 {
+                    return Vector2::zeros();
+                }
+
+                let phase = (2.0 * std::f32::consts::PI * elapsed / period).sin();
+                Vector2::new(amplitude.0, amplitude.1) * phase
+            }
+        }
+    }
+}
+
+/// The shape of the play area: what is at each of its edges, plus any walls and portals inside
+/// it. Positions are in arena coordinates, with the origin in the bottom left corner.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Arena {
+    #[serde(skip)]
+    pub width: f32,
+    #[serde(skip)]
+    pub height: f32,
+    pub left: Edge,
+    pub top: Edge,
+    pub right: Edge,
+    pub bottom: Edge,
+    pub walls: Vec<Wall>,
+    pub portals: Vec<Portal>,
+}
+
+impl Default for Arena {
+    /// Walled off on every side but the bottom, with nothing inside.
+    fn default() -> Arena {
+        Arena {
+            width: ARENA_WIDTH,
+            height: ARENA_HEIGHT,
+            left: Edge::Wall,
+            top: Edge::Wall,
+            right: Edge::Wall,
+            bottom: Edge::Lethal,
+            walls: vec![],
+            portals: vec![],
+        }
+    }
+}
+
+impl Arena {
+    /// Each edge along with which side it is on, the normal pointing into the arena and the
+    /// middle of the edge.
+    pub fn edges(&self) -> [(Side, Edge, Unit<Vector2<f32>>, Vector2<f32>); 4] {
+        let (width, height) = (self.width, self.height);
+
+        [
+            (
+                Side::Left,
+                self.left,
+                Vector2::x_axis(),
+                Vector2::new(0.0, height / 2.0),
+            ),
+            (
+                Side::Top,
+                self.top,
+                -Vector2::y_axis(),
+                Vector2::new(width / 2.0, height),
+            ),
+            (
+                Side::Right,
+                self.right,
+                -Vector2::x_axis(),
+                Vector2::new(width, height / 2.0),
+            ),
+            (
+                Side::Bottom,
+                self.bottom,
+                Vector2::y_axis(),
+                Vector2::new(width / 2.0, 0.0),
+            ),
+        ]
+    }
+}
+
+/// One of the four edges of the arena.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Side {
+    Left,
+    Top,
+    Right,
+    Bottom,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Edge {
+    /// Balls bounce off of it.
+    Wall,
+    /// Balls are lost when they reach it.
+    Lethal,
+}
+
+/// A static obstacle balls bounce off of.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Wall {
+    Segment {
+        from: (f32, f32),
+        to: (f32, f32),
+    },
+    /// At least 3 points, going counter-clockwise around a convex polygon.
+    Polygon {
+        points: Vec<(f32, f32)>,
+    },
+}
+
+impl Wall {
+    pub fn points(&self) -> Vec<Point2<f32>> {
+        match self {
+            Wall::Segment { from, to } => {
+                vec![Point2::new(from.0, from.1), Point2::new(to.0, to.1)]
+            }
+            Wall::Polygon { points } => points.iter().map(|&(x, y)| Point2::new(x, y)).collect(),
+        }
+    }
+
+    /// Checks that the wall has a shape: a segment needs two different ends, and a polygon's
+    /// points need to turn left at every corner and go around exactly once.
+    pub fn check(&self) -> Result<(), String> {
+        let points = self.points();
+        if points
+            .iter()
+            .any(|point| !point.x.is_finite() || !point.y.is_finite())
+        {
+            return Err(format!("Wall has a point that isn't a number: {:?}", self));
+        }
+
+        match self {
+            Wall::Segment { .. } if points[0] == points[1] => {
+                Err(format!("Wall segment has no length: {:?}", self))
+            }
+            Wall::Segment { .. } => Ok(()),
+            Wall::Polygon { .. } if points.len() < 3 => {
+                Err(format!("Wall polygon needs at least 3 points: {:?}", self))
+            }
+            Wall::Polygon { .. } => {
+                let mut turned = 0.0;
+                for i in 0..points.len() {
+                    let edge = points[(i + 1) % points.len()] - points[i];
+                    let next = points[(i + 2) % points.len()] - points[(i + 1) % points.len()];
+                    let cross = edge.x * next.y - edge.y * next.x;
+                    if cross <= 0.0 {
+                        return Err(format!(
+                            "Wall polygon isn't convex and counter-clockwise: {:?}",
+                            self
+                        ));
+                    }
+                    turned += cross.atan2(edge.dot(&next));
+                }
+
+                // a star turns left at every point too, but goes around more than once
+                if (turned - 2.0 * PI).abs() > 1e-3 {
+                    return Err(format!("Wall polygon crosses itself: {:?}", self));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// The collision shape of the wall, or `None` if it doesn't pass `check`.
+    pub fn shape(&self) -> Option<ShapeHandle<f32>> {
+        if self.check().is_err() {
+            return None;
+        }
+        let points = self.points();
+
+        match self {
+            Wall::Segment { .. } => Some(ShapeHandle::new(Segment::new(points[0], points[1]))),
+            Wall::Polygon { .. } => ConvexPolygon::try_new(points).map(ShapeHandle::new),
+        }
+    }
+}
+
+/// Sends any ball that comes within `radius` of the entrance out of the exit, keeping its
+/// velocity. Portals only work one way.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Portal {
+    pub entrance: (f32, f32),
+    pub exit: (f32, f32),
+    pub radius: f32,
+}
+
+/// How far through the campaign the player has got.
+#[derive(Debug, Clone)]
+pub struct Progress {
+    pub level: usize,
+    /// Balls left to serve once the one in play is lost.
+    pub lives: u32,
+    pub score: u32,
+}
+
+impl Default for Progress {
+    fn default() -> Progress {
+        Progress {
+            level: 0,
+            lives: STARTING_LIVES,
+            score: 0,
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{
+    replay::RecordingSystem,
+    systems::{
+        AutoplaySystem, BallCollisionSystemDesc, BallDestroyerSystemDesc, BallMovementSystem,
+        BallNudgeSystemDesc, BrickSystemDesc, LaserSystemDesc, PaddleInputSystem, PaddleSystem,
+        PathSystem, PortalSystem, PowerUpSystemDesc, SpeedSystemDesc, StuckBallSystemDesc, Timed,
+        TimedDesc, WorldUpdateSystem,
+    },
+};
+use amethyst::prelude::GameDataBuilder;
+
+pub mod collision;
+pub mod components;
+pub mod config;
+pub mod env;
+pub mod events;
+pub mod game;
+pub mod headless;
+pub mod high_score;
+pub mod level;
+pub mod physics;
+pub mod replay;
+pub mod save;
+pub mod settings;
+pub mod systems;
+
+/// Adds the systems that play the game, which are the same with or without a window. The
+/// collision systems are timed, for the bench to report on.
+pub fn gameplay_systems<'a, 'b>(game_data: GameDataBuilder<'a, 'b>) -> GameDataBuilder<'a, 'b> {
+    game_data
+        .with(PaddleInputSystem, "paddle_input_system", &["input_system"])
+        .with(AutoplaySystem::new(), "autoplay_system", &[])
+        .with(
+            RecordingSystem,
+            "recording_system",
+            &["paddle_input_system", "autoplay_system"],
+        )
+        .with(
+            PaddleSystem::new(),
+            "paddle_system",
+            &["transform_system", "paddle_input_system", "autoplay_system"],
+        )
+        .with(
+            BallMovementSystem,
+            "ball_movement_system",
+            &["transform_system", "paddle_system"],
+        )
+        .with(PortalSystem, "portal_system", &["ball_movement_system"])
+        .with(PathSystem, "path_system", &["transform_system"])
+        .with(
+            Timed::new("world_update_system", WorldUpdateSystem::default()),
+            "world_update_system",
+            &[
+                "transform_system",
+                "paddle_system",
+                "ball_movement_system",
+                "portal_system",
+                "path_system",
+            ],
+        )
+        .with_system_desc(
+            TimedDesc::new("ball_collision_system", BallCollisionSystemDesc::default()),
+            "ball_collision_system",
+            &["ball_movement_system", "world_update_system"],
+        )
+        .with_system_desc(
+            BallNudgeSystemDesc::default(),
+            "ball_nudge_system",
+            &["ball_collision_system"],
+        )
+        .with_system_desc(
+            BallDestroyerSystemDesc::default(),
+            "ball_destroyer_system",
+            &["ball_movement_system", "world_update_system"],
+        )
+        .with_system_desc(
+            BrickSystemDesc::default(),
+            "brick_system",
+            &["world_update_system", "ball_collision_system"],
+        )
+        .with_system_desc(
+            SpeedSystemDesc::default(),
+            "speed_system",
+            &[
+                "ball_collision_system",
+                "ball_nudge_system",
+                "ball_destroyer_system",
+            ],
+        )
+        .with_system_desc(
+            PowerUpSystemDesc::default(),
+            "power_up_system",
+            &[
+                "world_update_system",
+                "ball_collision_system",
+                "speed_system",
+            ],
+        )
+        .with_system_desc(
+            StuckBallSystemDesc::default(),
+            "stuck_ball_system",
+            &["ball_collision_system", "power_up_system"],
+        )
+        .with_system_desc(
+            LaserSystemDesc::default(),
+            "laser_system",
+            &["world_update_system", "brick_system"],
+        )
+}
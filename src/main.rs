@@ -1,35 +1,201 @@
-use crate::{
-    game::GameState,
-    systems::{
-        BallCollisionSystem, BallDestroyerSystem, BallMovementSystem, PaddleSystem,
-        WorldUpdateSystem,
-    },
-};
 use amethyst::{
+    config::Config,
     core::transform::TransformBundle,
     input::{InputBundle, StringBindings},
     prelude::*,
     renderer::{
-        plugins::{RenderFlat2D, RenderToWindow},
+        plugins::{RenderDebugLines, RenderFlat2D, RenderToWindow},
         types::DefaultBackend,
         RenderingBundle,
     },
+    ui::{RenderUi, UiBundle},
     utils::application_root_dir,
 };
+use simple_brick_breaker::{
+    config::GameplayConfig,
+    game::{GameState, LaunchOptions, BENCH_BALLS},
+    gameplay_systems,
+    headless::{run_bench, run_soak, Simulation},
+    high_score::HighScores,
+    level::Campaign,
+    replay::Replay,
+    settings::{DisplayOverrides, Settings},
+};
+use std::path::PathBuf;
+use structopt::StructOpt;
 
-mod collision;
-mod components;
-mod game;
-mod systems;
+#[derive(Debug, StructOpt)]
+#[structopt(name = "simple_brick_breaker")]
+struct Options {
+    /// Start straight into this level, counting from 1
+    #[structopt(long)]
+    level: Option<usize>,
+    /// Seed the game's random numbers, so the same inputs play out the same way
+    #[structopt(long)]
+    seed: Option<u64>,
+    /// Play out a recorded game, with the gameplay config it was recorded with
+    #[structopt(
+        long,
+        parse(from_os_str),
+        conflicts_with_all = &["level", "seed", "record", "config"]
+    )]
+    replay: Option<PathBuf>,
+    /// Record the game to this file, to play out again with --replay
+    #[structopt(long, parse(from_os_str))]
+    record: Option<PathBuf>,
+    /// Run without a window, with the bot playing unless a replay is given, and print where the
+    /// game got to
+    #[structopt(long)]
+    headless: bool,
+    /// Stop a headless run after this many steps
+    #[structopt(long, requires = "headless")]
+    ticks: Option<u64>,
+    /// Use this gameplay config instead of config/gameplay.ron
+    #[structopt(long, parse(from_os_str))]
+    config: Option<PathBuf>,
+    /// Play through this campaign instead of config/campaign.ron
+    #[structopt(long, parse(from_os_str))]
+    campaign: Option<PathBuf>,
+    /// Play in a window this time, whatever the fullscreen setting says
+    #[structopt(long, conflicts_with = "fullscreen")]
+    windowed: bool,
+    /// Play fullscreen this time, whatever the fullscreen setting says
+    #[structopt(long)]
+    fullscreen: bool,
+    /// Have the bot play without a window until it has lost this many balls, checking that
+    /// nothing goes wrong
+    #[structopt(long, conflicts_with_all = &["headless", "replay", "record"])]
+    soak: Option<usize>,
+    /// Run this many steps of a stress level without a window, as fast as possible, and report
+    /// how long they took
+    #[structopt(
+        long,
+        conflicts_with_all = &["headless", "replay", "record", "soak", "level", "campaign"]
+    )]
+    bench: Option<u64>,
+}
 
 fn main() -> amethyst::Result<()> {
     amethyst::start_logger(Default::default());
 
+    let options = Options::from_args();
+
     let app_root = application_root_dir()?;
 
     let assets_dir = app_root.join("assets");
     let config_dir = app_root.join("config");
     let display_config_path = config_dir.join("display.ron");
+    let gameplay_config_path = options
+        .config
+        .clone()
+        .unwrap_or_else(|| config_dir.join("gameplay.ron"));
+    let gameplay_config = GameplayConfig::load_checked(gameplay_config_path)?;
+    let campaign_path = options
+        .campaign
+        .clone()
+        .unwrap_or_else(|| config_dir.join("campaign.ron"));
+    let campaign = Campaign::load_with_levels(campaign_path)?;
+    let level = level_index(options.level, campaign.levels.len())?;
+
+    if let Some(ticks) = options.bench {
+        let game_data = GameDataBuilder::default()
+            .with_bundle(InputBundle::<StringBindings>::new())?
+            .with_bundle(TransformBundle::new())?;
+        let mut simulation = Simulation::new(
+            &assets_dir,
+            gameplay_systems(game_data),
+            gameplay_config,
+            Campaign::load_with_levels(config_dir.join("bench.ron"))?,
+            LaunchOptions {
+                seed: Some(options.seed.unwrap_or(0)),
+                ..LaunchOptions::default()
+            },
+        )?;
+
+        let report = run_bench(&mut simulation, ticks, BENCH_BALLS);
+        println!(
+            "{} ticks with {} balls and {} bricks in {:.2?}: {:.0} ticks/s",
+            report.ticks,
+            report.balls,
+            report.bricks,
+            report.elapsed,
+            report.ticks_per_second()
+        );
+        println!("  WorldUpdateSystem:   {:.2?}", report.world_update);
+        println!("  BallCollisionSystem: {:.2?}", report.ball_collision);
+        return Ok(());
+    }
+
+    if let Some(balls) = options.soak {
+        let game_data = GameDataBuilder::default()
+            .with_bundle(InputBundle::<StringBindings>::new())?
+            .with_bundle(TransformBundle::new())?;
+        let mut simulation = Simulation::new(
+            &assets_dir,
+            gameplay_systems(game_data),
+            gameplay_config,
+            campaign,
+            LaunchOptions {
+                seed: Some(options.seed.unwrap_or_else(rand::random)),
+                level,
+                ..LaunchOptions::default()
+            },
+        )?;
+
+        let report = run_soak(&mut simulation, balls);
+        println!("{:#?}", report);
+        if !report.passed() {
+            return Err(amethyst::Error::from_string("Soak test failed"));
+        }
+        return Ok(());
+    }
+
+    let launch_options = LaunchOptions {
+        level,
+        seed: options.seed,
+        replay: options.replay.as_ref().map(Replay::load).transpose()?,
+        record: options.record.clone(),
+    };
+
+    if options.headless {
+        if options.ticks.is_none() && launch_options.replay.is_none() {
+            return Err(amethyst::Error::from_string(
+                "A headless run needs --ticks or --replay to know when to stop",
+            ));
+        }
+
+        let game_data = GameDataBuilder::default()
+            .with_bundle(InputBundle::<StringBindings>::new())?
+            .with_bundle(TransformBundle::new())?;
+        let mut simulation = Simulation::new(
+            &assets_dir,
+            gameplay_systems(game_data),
+            gameplay_config,
+            campaign,
+            launch_options,
+        )?;
+
+        let mut ticks = 0;
+        while !simulation.finished() && options.ticks.map_or(true, |max| ticks < max) {
+            simulation.step();
+            ticks += 1;
+        }
+        simulation.stop();
+        println!("After {} ticks: {:#?}", ticks, simulation.summary());
+        return Ok(());
+    }
+
+    let settings = Settings::load_or_default();
+    let display_overrides = DisplayOverrides {
+        fullscreen: if options.windowed {
+            Some(false)
+        } else if options.fullscreen {
+            Some(true)
+        } else {
+            None
+        },
+    };
+    let (frame_limit_strategy, max_fps) = settings.frame_limit();
 
     let game_data = GameDataBuilder::default()
         .with_bundle(
@@ -38,38 +204,36 @@ fn main() -> amethyst::Result<()> {
                     RenderToWindow::from_config_path(display_config_path)?
                         .with_clear([0.0, 0.0, 0.0, 1.0]),
                 )
-                .with_plugin(RenderFlat2D::default()),
+                .with_plugin(RenderFlat2D::default())
+                .with_plugin(RenderDebugLines::default())
+                .with_plugin(RenderUi::default()),
         )?
         .with_bundle(InputBundle::<StringBindings>::new())?
-        .with_bundle(TransformBundle::new())?
-        .with(
-            PaddleSystem::new(),
-            "paddle_system",
-            &["input_system", "transform_system"],
-        )
-        .with(
-            BallMovementSystem,
-            "ball_movement_system",
-            &["transform_system", "paddle_system"],
-        )
-        .with(
-            WorldUpdateSystem::default(),
-            "world_update_system",
-            &["transform_system", "paddle_system", "ball_movement_system"],
-        )
-        .with(
-            BallCollisionSystem,
-            "ball_collision_system",
-            &["ball_movement_system", "world_update_system"],
-        )
-        .with(
-            BallDestroyerSystem,
-            "ball_destroyer_system",
-            &["ball_movement_system", "world_update_system"],
-        );
+        .with_bundle(UiBundle::<StringBindings>::new())?
+        .with_bundle(TransformBundle::new())?;
+    let game_data = gameplay_systems(game_data);
 
-    let mut game = Application::new(assets_dir, GameState, game_data)?;
+    let mut game = Application::build(assets_dir, GameState::new(launch_options))?
+        .with_frame_limit(frame_limit_strategy, max_fps)
+        .with_resource(gameplay_config)
+        .with_resource(campaign)
+        .with_resource(HighScores::load_or_default())
+        .with_resource(settings)
+        .with_resource(display_overrides)
+        .build(game_data)?;
     game.run();
 
     Ok(())
 }
+
+/// Turns a level given on the command line, counting from 1, into the campaign's index for it.
+fn level_index(level: Option<usize>, levels: usize) -> amethyst::Result<Option<usize>> {
+    match level {
+        Some(level) if level >= 1 && level <= levels => Ok(Some(level - 1)),
+        Some(level) => Err(amethyst::Error::from_string(format!(
+            "There is no level {}; the campaign has levels 1 to {}",
+            level, levels
+        ))),
+        None => Ok(None),
+    }
+}
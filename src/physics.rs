@@ -0,0 +1,160 @@
+//! How balls bounce, turn and speed up, as plain functions of their velocity and what they ran
+//! into, so that the systems stay small and the arithmetic can be tested on its own.
+
+use crate::game::{BALL_MAX_ROTATION, BALL_MAX_SPEED};
+use amethyst::core::math::{Rotation2, Unit, Vector2};
+
+/// The weight given to contacts that are only just touching when averaging normals.
+const MIN_CONTACT_WEIGHT: f32 = 0.01;
+
+/// A surface a ball has run into.
+#[derive(Debug, Copy, Clone)]
+pub struct Surface {
+    /// Points from the ball towards the surface.
+    pub normal: Unit<Vector2<f32>>,
+    pub depth: f32,
+    /// How fast the surface itself is moving.
+    pub velocity: Vector2<f32>,
+}
+
+impl Surface {
+    /// Whether a ball moving at `velocity` is heading into the surface, as seen from the surface.
+    pub fn is_approached_by(&self, velocity: Vector2<f32>) -> bool {
+        (velocity - self.velocity).dot(&self.normal) > 0.0
+    }
+}
+
+/// A ball's velocity straight after bouncing off of everything it ran into at once, along with
+/// what it bounced off of.
+#[derive(Debug, Copy, Clone)]
+pub struct Bounce {
+    pub velocity: Vector2<f32>,
+    /// The depth-weighted average of the surfaces' normals.
+    pub normal: Unit<Vector2<f32>>,
+    /// The average of the surfaces' velocities.
+    pub surface_velocity: Vector2<f32>,
+    /// How far either way the ball can be turned without heading back into any of the
+    /// surfaces, and never more than `BALL_MAX_ROTATION`.
+    pub max_rotation: f32,
+}
+
+impl Bounce {
+    /// Whether a ball moving at `velocity` is heading away from the surfaces it bounced off of.
+    pub fn is_left_by(&self, velocity: Vector2<f32>) -> bool {
+        (velocity - self.surface_velocity).dot(&self.normal) <= 0.0
+    }
+}
+
+/// Reflects the velocity a single time off of surfaces it hit simultaneously, as seen from the
+/// surfaces moving along with them, so that a ball hitting a corner or two bricks at once isn't
+/// reflected twice. `surfaces` shouldn't be empty.
+pub fn bounce(velocity: Vector2<f32>, surfaces: &[Surface]) -> Bounce {
+    let normal = average_normal(surfaces);
+    let surface_velocity = surfaces
+        .iter()
+        .fold(Vector2::zeros(), |sum, surface| sum + surface.velocity)
+        / surfaces.len() as f32;
+    let velocity = limit_speed(
+        reflect(velocity - surface_velocity, normal) + surface_velocity,
+        BALL_MAX_SPEED,
+    );
+
+    let max_rotation = surfaces
+        .iter()
+        .map(|surface| {
+            (velocity.dot(&surface.normal) / velocity.norm())
+                .asin()
+                .abs()
+        })
+        .fold(BALL_MAX_ROTATION, f32::min)
+        .max(0.0);
+
+    Bounce {
+        velocity,
+        normal,
+        surface_velocity,
+        max_rotation,
+    }
+}
+
+/// Turns a bounced ball by `rotation` radians, which should be no more than the bounce's
+/// `max_rotation` either way, then steepens it to at least `min_angle` radians from horizontal.
+/// Either is skipped if it would send the ball back into what it bounced off of.
+pub fn deflect(bounce: &Bounce, rotation: f32, min_angle: f32) -> Vector2<f32> {
+    let mut velocity = bounce.velocity;
+
+    let rotated = Rotation2::new(rotation) * velocity;
+    if bounce.is_left_by(rotated) {
+        velocity = rotated;
+    }
+
+    let steepened = enforce_min_angle(velocity, min_angle);
+    if bounce.is_left_by(steepened) {
+        velocity = steepened;
+    }
+
+    // turning can round the speed up a little
+    limit_speed(velocity, BALL_MAX_SPEED)
+}
+
+/// Reflects the velocity off of a surface with the given normal, if it is heading into it.
+pub fn reflect(velocity: Vector2<f32>, normal: Unit<Vector2<f32>>) -> Vector2<f32> {
+    if velocity.dot(&normal) > 0.0 {
+        velocity - 2.0 * velocity.dot(&normal) * *normal
+    } else {
+        velocity
+    }
+}
+
+/// Averages the normals of simultaneous contacts, weighted by penetration depth, falling back to
+/// the deepest one if they cancel out.
+pub fn average_normal(surfaces: &[Surface]) -> Unit<Vector2<f32>> {
+    let sum = surfaces.iter().fold(Vector2::zeros(), |sum, surface| {
+        sum + *surface.normal * surface.depth.max(MIN_CONTACT_WEIGHT)
+    });
+
+    Unit::try_new(sum, f32::EPSILON).unwrap_or_else(|| {
+        surfaces
+            .iter()
+            .max_by(|a, b| a.depth.total_cmp(&b.depth))
+            .unwrap()
+            .normal
+    })
+}
+
+/// Steepens the velocity so that it is at least `min_angle` radians away from horizontal,
+/// keeping its speed and which way it is heading.
+pub fn enforce_min_angle(velocity: Vector2<f32>, min_angle: f32) -> Vector2<f32> {
+    let speed = velocity.norm();
+    if speed == 0.0 || velocity.y.abs() >= speed * min_angle.sin() {
+        return velocity;
+    }
+
+    let x = if velocity.x < 0.0 { -1.0 } else { 1.0 };
+    let y = if velocity.y < 0.0 { -1.0 } else { 1.0 };
+    Vector2::new(x * min_angle.cos(), y * min_angle.sin()) * speed
+}
+
+/// The velocity at the given speed, or as near to it as `BALL_MAX_SPEED` allows, keeping its
+/// direction. A ball that isn't moving stays put. The result can be off by as much as rounding.
+pub fn set_speed(velocity: Vector2<f32>, speed: f32) -> Vector2<f32> {
+    let speed = speed.max(0.0).min(BALL_MAX_SPEED);
+    let norm = velocity.norm();
+    if norm == 0.0 {
+        return velocity;
+    }
+
+    velocity * (speed / norm)
+}
+
+/// Slows the velocity down to `max_speed`, or `BALL_MAX_SPEED` if that is lower, if it is any
+/// faster.
+pub fn limit_speed(velocity: Vector2<f32>, max_speed: f32) -> Vector2<f32> {
+    let max_speed = max_speed.min(BALL_MAX_SPEED);
+    let norm = velocity.norm();
+    if norm > max_speed {
+        velocity * (max_speed / norm)
+    } else {
+        velocity
+    }
+}
@@ -0,0 +1,62 @@
+use crate::{config::GameplayConfig, systems::PaddleInput};
+use amethyst::{
+    config::{Config, ConfigError},
+    core::timing::Time,
+    derive::SystemDesc,
+    ecs::{Read, System, SystemData, Write},
+};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// A game recorded a frame at a time, with everything needed to play it out again exactly: the
+/// seed and level it started from, the gameplay config and paddle smoothing it was played with,
+/// and how long each frame took and what the paddle was asked to do in it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub level: usize,
+    pub config: GameplayConfig,
+    pub paddle_smoothing: f32,
+    pub frames: Vec<ReplayFrame>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayFrame {
+    pub delta_seconds: f32,
+    pub input: PaddleInput,
+}
+
+/// The replay being recorded, written out to `path` once the game is over or the window is
+/// closed.
+#[derive(Debug)]
+pub struct Recording {
+    pub path: PathBuf,
+    pub replay: Replay,
+}
+
+impl Recording {
+    pub fn save(&self) -> Result<(), ConfigError> {
+        self.replay.write(&self.path)
+    }
+}
+
+/// Adds every frame's paddle input to the recording, if there is one.
+#[derive(Default, SystemDesc)]
+pub struct RecordingSystem;
+
+impl<'s> System<'s> for RecordingSystem {
+    type SystemData = (
+        Read<'s, Time>,
+        Read<'s, PaddleInput>,
+        Option<Write<'s, Recording>>,
+    );
+
+    fn run(&mut self, (time, input, recording): Self::SystemData) {
+        if let Some(mut recording) = recording {
+            recording.replay.frames.push(ReplayFrame {
+                delta_seconds: time.delta_seconds(),
+                input: input.clone(),
+            });
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use crate::{
+    components::{Ball, Brick, Paddle, PathFollower, PowerUp, PowerUpKind, Stuck},
+    config::SpeedProgress,
+    game::{
+        build_arena, clear_level, initialize_ball, initialize_brick, initialize_paddle,
+        initialize_power_up, GameRng, GameSprites,
+    },
+    level::{Campaign, Path, Progress},
+    systems::ActivePowerUps,
+};
+use amethyst::{
+    config::{Config, ConfigError},
+    core::{math::Vector2, transform::Transform},
+    ecs::{Join, WorldExt},
+    prelude::World,
+    renderer::SpriteRender,
+};
+use log::{error, warn};
+use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::PathBuf};
+
+/// Where a file belonging to the game goes in the user's data directory.
+pub fn user_data_path(file: &str) -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("simple_brick_breaker").join(file))
+}
+
+/// Writes a config file into the user's data directory, creating the directory if need be.
+pub fn write_user_data<C: Config>(config: &C, file: &str) -> Result<(), ConfigError> {
+    let path = user_data_path(file).ok_or_else(|| {
+        ConfigError::File(io::Error::new(
+            io::ErrorKind::NotFound,
+            "no user data directory",
+        ))
+    })?;
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(ConfigError::File)?;
+    }
+    config.write(path)
+}
+
+const SAVE_FILE: &str = "save.ron";
+
+/// Everything needed to pick a game back up where it was left. Lasers in flight are not kept.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveGame {
+    pub level: usize,
+    pub lives: u32,
+    pub score: u32,
+    pub speed: SpeedProgress,
+    pub seed: u64,
+    pub rng: Pcg32,
+    pub active_power_ups: ActivePowerUps,
+    pub paddle: SavedPaddle,
+    pub balls: Vec<SavedBall>,
+    pub bricks: Vec<SavedBrick>,
+    pub power_ups: Vec<SavedPowerUp>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedPaddle {
+    pub x: f32,
+    pub width: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedBall {
+    pub position: (f32, f32),
+    pub velocity: (f32, f32),
+    /// The offset and speed of a ball held on the paddle.
+    pub stuck: Option<(f32, f32)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedBrick {
+    pub position: (f32, f32),
+    pub row: usize,
+    pub destructible: bool,
+    pub sprite_number: usize,
+    pub path: Option<SavedPath>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedPath {
+    pub path: Path,
+    pub spin: f32,
+    pub origin: (f32, f32),
+    pub elapsed: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedPowerUp {
+    pub position: (f32, f32),
+    pub kind: PowerUpKind,
+}
+
+impl SaveGame {
+    /// Takes a snapshot of the game in progress.
+    pub fn capture(world: &World) -> SaveGame {
+        let progress = world.read_resource::<Progress>();
+        let rng = world.read_resource::<GameRng>();
+        let transforms = world.read_storage::<Transform>();
+
+        let paddle = (&world.read_storage::<Paddle>(), &transforms)
+            .join()
+            .next()
+            .map(|(paddle, transform)| SavedPaddle {
+                x: transform.translation().x,
+                width: paddle.width,
+            })
+            .expect("No paddle to save");
+
+        let balls = (
+            &world.read_storage::<Ball>(),
+            &transforms,
+            world.read_storage::<Stuck>().maybe(),
+        )
+            .join()
+            .map(|(ball, transform, stuck)| SavedBall {
+                position: position(transform),
+                velocity: (ball.velocity.x, ball.velocity.y),
+                stuck: stuck.map(|stuck| (stuck.offset, stuck.speed)),
+            })
+            .collect();
+
+        let bricks = (
+            &world.read_storage::<Brick>(),
+            &transforms,
+            &world.read_storage::<SpriteRender>(),
+            world.read_storage::<PathFollower>().maybe(),
+        )
+            .join()
+            .map(|(brick, transform, render, follower)| SavedBrick {
+                position: position(transform),
+                row: brick.row,
+                destructible: brick.destructible,
+                sprite_number: render.sprite_number,
+                path: follower.map(|follower| SavedPath {
+                    path: follower.path.clone(),
+                    spin: follower.spin,
+                    origin: (follower.origin.x, follower.origin.y),
+                    elapsed: follower.elapsed,
+                }),
+            })
+            .collect();
+
+        let power_ups = (&world.read_storage::<PowerUp>(), &transforms)
+            .join()
+            .map(|(power_up, transform)| SavedPowerUp {
+                position: position(transform),
+                kind: power_up.kind,
+            })
+            .collect();
+
+        SaveGame {
+            level: progress.level,
+            lives: progress.lives,
+            score: progress.score,
+            speed: world.read_resource::<SpeedProgress>().clone(),
+            seed: rng.seed,
+            rng: rng.rng.clone(),
+            active_power_ups: world.read_resource::<ActivePowerUps>().clone(),
+            paddle,
+            balls,
+            bricks,
+            power_ups,
+        }
+    }
+
+    /// Replaces whatever is in play with the saved game, rebuilding the level's arena from the
+    /// campaign.
+    pub fn restore(self, world: &mut World) {
+        clear_level(world);
+
+        world.insert(Progress {
+            level: self.level,
+            lives: self.lives,
+            score: self.score,
+        });
+        world.insert(self.speed);
+        world.insert(GameRng {
+            seed: self.seed,
+            rng: self.rng,
+        });
+        world.insert(self.active_power_ups);
+
+        let arena = world.read_resource::<Campaign>().level(self.level).arena;
+        build_arena(world, &arena);
+
+        let sprite_sheet = world.read_resource::<GameSprites>().sprite_sheet.clone();
+        initialize_paddle(
+            world,
+            sprite_sheet.clone(),
+            self.paddle.x,
+            self.paddle.width,
+        );
+
+        for brick in self.bricks {
+            let follower = brick.path.map(|saved| PathFollower {
+                elapsed: saved.elapsed,
+                ..PathFollower::new(saved.path, saved.spin, vector(saved.origin))
+            });
+
+            initialize_brick(
+                world,
+                sprite_sheet.clone(),
+                vector(brick.position),
+                Brick {
+                    row: brick.row,
+                    destructible: brick.destructible,
+                },
+                brick.sprite_number,
+                follower,
+            );
+        }
+
+        for ball in self.balls {
+            let entity = initialize_ball(
+                world,
+                sprite_sheet.clone(),
+                vector(ball.position),
+                vector(ball.velocity),
+            );
+
+            if let Some((offset, speed)) = ball.stuck {
+                world
+                    .write_storage::<Stuck>()
+                    .insert(entity, Stuck { offset, speed })
+                    .expect("Error sticking ball");
+            }
+        }
+
+        for power_up in self.power_ups {
+            initialize_power_up(
+                world,
+                sprite_sheet.clone(),
+                vector(power_up.position),
+                power_up.kind,
+            );
+        }
+    }
+
+    pub fn save(&self) -> Result<(), ConfigError> {
+        write_user_data(self, SAVE_FILE)
+    }
+
+    /// Picks the saved game back up if there is one, returning whether there was. It is removed
+    /// once restored so the same game can't be resumed twice, while one that can't be loaded is
+    /// moved aside to a `.bad` file.
+    pub fn resume(world: &mut World) -> bool {
+        let path = match user_data_path(SAVE_FILE).filter(|path| path.exists()) {
+            Some(path) => path,
+            None => return false,
+        };
+
+        match SaveGame::load(&path) {
+            Ok(save) => {
+                save.restore(world);
+                SaveGame::discard();
+                true
+            }
+            Err(err) => {
+                error!("Error loading saved game {:?}: {}", path, err);
+                let bad_path = path.with_file_name(format!("{}.bad", SAVE_FILE));
+                if let Err(err) = fs::rename(&path, &bad_path) {
+                    warn!("Error moving saved game to {:?}: {}", bad_path, err);
+                }
+                false
+            }
+        }
+    }
+
+    /// Removes the saved game, if there is one, e.g. once it is over.
+    pub fn discard() {
+        if let Some(path) = user_data_path(SAVE_FILE).filter(|path| path.exists()) {
+            if let Err(err) = fs::remove_file(&path) {
+                warn!("Error removing saved game {:?}: {}", path, err);
+            }
+        }
+    }
+}
+
+fn position(transform: &Transform) -> (f32, f32) {
+    let translation = transform.translation();
+    (translation.x, translation.y)
+}
+
+fn vector((x, y): (f32, f32)) -> Vector2<f32> {
+    Vector2::new(x, y)
+}
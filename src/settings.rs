@@ -0,0 +1,598 @@
+use crate::{
+    components::Brick,
+    game::{save_game, PADDLE_DISTANCE_VELOCITY_RATIO, VSYNC_FRAME_RATE},
+    save::{user_data_path, write_user_data},
+};
+use amethyst::{
+    assets::Loader,
+    config::{Config, ConfigError},
+    core::{
+        frame_limiter::{FrameLimiter, FrameRateLimitStrategy},
+        timing::Time,
+    },
+    ecs::{Entity, Join},
+    input::{is_close_requested, is_key_down, Button, VirtualKeyCode},
+    prelude::{Builder, SimpleTrans, StateEvent, Trans, World, WorldExt},
+    renderer::{palette::Srgba, resources::Tint, SpriteRender},
+    ui::{Anchor, FontHandle, TtfFormat, UiText, UiTransform},
+    window::Window,
+    winit::{ElementState, Event, KeyboardInput, MouseButton, WindowEvent},
+    GameData, SimpleState, StateData,
+};
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// The player's preferences, kept in the user's data directory and changed from the options
+/// menu.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// How far the paddle's target moves from the middle of the arena for each pixel the mouse
+    /// does.
+    pub mouse_sensitivity: f32,
+    /// How many seconds the paddle takes to close the distance to the mouse, below its top
+    /// speed, under proportional paddle control. Lower is snappier.
+    pub paddle_smoothing: f32,
+    /// From 0 to 1. The game has no sounds yet, so this is only kept for when it does.
+    pub volume: f32,
+    pub fullscreen: bool,
+    /// Caps the frame rate at `VSYNC_FRAME_RATE`. The renderer doesn't let the swapchain's
+    /// present mode be picked, so this is as close as the game can get to real vsync.
+    pub vsync: bool,
+    pub keybinds: Keybinds,
+    pub palette: Palette,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            mouse_sensitivity: 1.0,
+            paddle_smoothing: PADDLE_DISTANCE_VELOCITY_RATIO,
+            volume: 1.0,
+            fullscreen: false,
+            vsync: true,
+            keybinds: Keybinds::default(),
+            palette: Palette::Classic,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Keybinds {
+    /// Move the paddle when it is controlled by acceleration.
+    pub left: Button,
+    pub right: Button,
+    /// Launches balls held on a sticky paddle.
+    pub release: Button,
+    /// Fires the paddle's lasers.
+    pub fire: Button,
+    /// Opens and closes the options menu.
+    pub options: VirtualKeyCode,
+}
+
+impl Default for Keybinds {
+    fn default() -> Keybinds {
+        Keybinds {
+            left: Button::Key(VirtualKeyCode::Left),
+            right: Button::Key(VirtualKeyCode::Right),
+            release: Button::Mouse(MouseButton::Left),
+            fire: Button::Mouse(MouseButton::Left),
+            options: VirtualKeyCode::Escape,
+        }
+    }
+}
+
+/// The colours bricks are drawn in. Bricks of different colours use different sprites too, so
+/// they can be told apart whatever the palette.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Palette {
+    /// The sprites as they are, all white.
+    Classic,
+    /// A different hue for each colour.
+    Colour,
+    /// Hues that stay distinct with the common kinds of colour blindness.
+    Colourblind,
+}
+
+impl Palette {
+    pub fn next(self) -> Palette {
+        match self {
+            Palette::Classic => Palette::Colour,
+            Palette::Colour => Palette::Colourblind,
+            Palette::Colourblind => Palette::Classic,
+        }
+    }
+
+    pub fn previous(self) -> Palette {
+        self.next().next()
+    }
+
+    /// The tint for a brick of the given colour, from 1 to 9.
+    pub fn brick_tint(self, colour: usize) -> Option<Tint> {
+        let (r, g, b) = match self {
+            Palette::Classic => return None,
+            Palette::Colour => [
+                (0.9, 0.2, 0.2),
+                (0.95, 0.55, 0.15),
+                (0.95, 0.9, 0.2),
+                (0.45, 0.85, 0.25),
+                (0.2, 0.8, 0.7),
+                (0.25, 0.6, 0.95),
+                (0.35, 0.35, 0.95),
+                (0.7, 0.35, 0.9),
+                (0.95, 0.45, 0.75),
+            ][(colour + 8) % 9],
+            // Okabe and Ito's palette, plus white
+            Palette::Colourblind => [
+                (0.9, 0.62, 0.0),
+                (0.34, 0.71, 0.91),
+                (0.0, 0.62, 0.45),
+                (0.94, 0.89, 0.26),
+                (0.0, 0.45, 0.7),
+                (0.84, 0.37, 0.0),
+                (0.8, 0.47, 0.65),
+                (1.0, 1.0, 1.0),
+                (0.6, 0.6, 0.6),
+            ][(colour + 8) % 9],
+        };
+
+        Some(Tint(Srgba::new(r, g, b, 1.0)))
+    }
+}
+
+const SETTINGS_FILE: &str = "settings.ron";
+
+/// The lowest and highest values the options menu allows.
+const MOUSE_SENSITIVITY_RANGE: (f32, f32) = (0.2, 3.0);
+const PADDLE_SMOOTHING_RANGE: (f32, f32) = (0.02, 0.5);
+const VOLUME_RANGE: (f32, f32) = (0.0, 1.0);
+
+impl Settings {
+    /// Loads the settings, falling back to the defaults if there aren't any yet or they can't be
+    /// read. Values the options menu wouldn't allow, e.g. from editing the file by hand, are
+    /// brought back within range.
+    pub fn load_or_default() -> Settings {
+        let mut settings = match user_data_path(SETTINGS_FILE) {
+            Some(path) if path.exists() => Settings::load(&path).unwrap_or_else(|err| {
+                error!("Error loading settings from {:?}: {}", path, err);
+                Settings::default()
+            }),
+            _ => Settings::default(),
+        };
+
+        settings.mouse_sensitivity = clamp(settings.mouse_sensitivity, MOUSE_SENSITIVITY_RANGE);
+        settings.paddle_smoothing = clamp(settings.paddle_smoothing, PADDLE_SMOOTHING_RANGE);
+        settings.volume = clamp(settings.volume, VOLUME_RANGE);
+        settings
+    }
+
+    pub fn save(&self) -> Result<(), ConfigError> {
+        write_user_data(self, SETTINGS_FILE)
+    }
+
+    pub fn frame_limit(&self) -> (FrameRateLimitStrategy, u32) {
+        if self.vsync {
+            (
+                FrameRateLimitStrategy::SleepAndYield(Duration::from_millis(2)),
+                VSYNC_FRAME_RATE,
+            )
+        } else {
+            (FrameRateLimitStrategy::Unlimited, 0)
+        }
+    }
+}
+
+/// Display choices made for this run only, e.g. on the command line. They take precedence over
+/// the `Settings` without being saved in them, until the player changes the same setting in the
+/// options menu.
+#[derive(Debug, Default)]
+pub struct DisplayOverrides {
+    pub fullscreen: Option<bool>,
+}
+
+/// The settings as they apply to this run, with any `DisplayOverrides` in place.
+fn effective_settings(world: &World) -> Settings {
+    let mut settings = world.read_resource::<Settings>().clone();
+    if let Some(fullscreen) = world
+        .try_fetch::<DisplayOverrides>()
+        .and_then(|overrides| overrides.fullscreen)
+    {
+        settings.fullscreen = fullscreen;
+    }
+    settings
+}
+
+/// Brings the window, frame rate and brick colours in line with the settings.
+pub fn apply_settings(world: &World) {
+    let settings = effective_settings(world);
+
+    if let Some(window) = world.try_fetch::<Window>() {
+        let monitor = if settings.fullscreen {
+            Some(window.get_current_monitor())
+        } else {
+            None
+        };
+        window.set_fullscreen(monitor);
+    }
+
+    let (strategy, fps) = settings.frame_limit();
+    world
+        .write_resource::<FrameLimiter>()
+        .set_rate(strategy, fps);
+
+    let entities = world.entities();
+    let bricks = world.read_storage::<Brick>();
+    let renders = world.read_storage::<SpriteRender>();
+    let mut tints = world.write_storage::<Tint>();
+    for (entity, brick, render) in (&entities, &bricks, &renders).join() {
+        if !brick.destructible {
+            continue;
+        }
+        match settings.palette.brick_tint(render.sprite_number - 1) {
+            Some(tint) => {
+                tints.insert(entity, tint).expect("Error tinting brick");
+            }
+            None => {
+                tints.remove(entity);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum OptionItem {
+    MouseSensitivity,
+    PaddleSmoothing,
+    Volume,
+    Fullscreen,
+    Vsync,
+    Palette,
+    LeftKey,
+    RightKey,
+    ReleaseKey,
+    FireKey,
+    OptionsKey,
+}
+
+const OPTION_ITEMS: [OptionItem; 11] = [
+    OptionItem::MouseSensitivity,
+    OptionItem::PaddleSmoothing,
+    OptionItem::Volume,
+    OptionItem::Fullscreen,
+    OptionItem::Vsync,
+    OptionItem::Palette,
+    OptionItem::LeftKey,
+    OptionItem::RightKey,
+    OptionItem::ReleaseKey,
+    OptionItem::FireKey,
+    OptionItem::OptionsKey,
+];
+
+/// The options menu, opened from the game with the options key. The game is paused while it is
+/// open. Up and down pick a setting, left and right change it and enter starts rebinding a key,
+/// which takes the next key or mouse button pressed. Changes take effect straight away and are
+/// saved when the menu is closed.
+pub struct OptionsState {
+    selected: usize,
+    rebinding: bool,
+    time_scale: f32,
+    font: Option<FontHandle>,
+    ui: Vec<Entity>,
+}
+
+impl Default for OptionsState {
+    fn default() -> OptionsState {
+        OptionsState {
+            selected: 0,
+            rebinding: false,
+            time_scale: 1.0,
+            font: None,
+            ui: vec![],
+        }
+    }
+}
+
+impl OptionsState {
+    fn add_line(&mut self, world: &mut World, line: usize) {
+        let transform = UiTransform::new(
+            format!("options_{}", line),
+            Anchor::TopMiddle,
+            Anchor::TopMiddle,
+            0.0,
+            -80.0 - line as f32 * 40.0,
+            1.0,
+            960.0,
+            40.0,
+        );
+        let text = UiText::new(
+            self.font.clone().unwrap(),
+            String::new(),
+            [1.0, 1.0, 1.0, 1.0],
+            28.0,
+        );
+
+        let entity = world.create_entity().with(transform).with(text).build();
+        self.ui.push(entity);
+    }
+
+    fn update_lines(&self, world: &mut World) {
+        let settings = effective_settings(world);
+        let mut texts = world.write_storage::<UiText>();
+
+        for (i, &entity) in self.ui.iter().enumerate() {
+            let text = match i {
+                0 => "OPTIONS".to_string(),
+                i => {
+                    let index = i - 2;
+                    let value = if self.rebinding && index == self.selected {
+                        "press a key...".to_string()
+                    } else {
+                        option_value(&settings, OPTION_ITEMS[index])
+                    };
+                    let cursor = if index == self.selected { ">" } else { " " };
+                    format!(
+                        "{} {:<20} {}",
+                        cursor,
+                        option_name(OPTION_ITEMS[index]),
+                        value
+                    )
+                }
+            };
+
+            if let Some(ui_text) = texts.get_mut(entity) {
+                ui_text.text = text;
+            }
+        }
+    }
+
+    /// Changes the selected setting by one step in the given direction and applies it.
+    fn change(&self, world: &mut World, direction: f32) {
+        if OPTION_ITEMS[self.selected] == OptionItem::Fullscreen {
+            // carry on from what the window shows, which from now on is up to the player
+            let overridden = world
+                .try_fetch_mut::<DisplayOverrides>()
+                .and_then(|mut overrides| overrides.fullscreen.take());
+            if let Some(fullscreen) = overridden {
+                world.write_resource::<Settings>().fullscreen = fullscreen;
+            }
+        }
+
+        self.adjust(&mut world.write_resource::<Settings>(), direction);
+        apply_settings(world);
+    }
+
+    /// Changes the selected setting by one step in the given direction.
+    fn adjust(&self, settings: &mut Settings, direction: f32) {
+        match OPTION_ITEMS[self.selected] {
+            OptionItem::MouseSensitivity => {
+                settings.mouse_sensitivity = step(
+                    settings.mouse_sensitivity,
+                    0.1 * direction,
+                    MOUSE_SENSITIVITY_RANGE,
+                );
+            }
+            OptionItem::PaddleSmoothing => {
+                settings.paddle_smoothing = step(
+                    settings.paddle_smoothing,
+                    0.02 * direction,
+                    PADDLE_SMOOTHING_RANGE,
+                );
+            }
+            OptionItem::Volume => {
+                settings.volume = step(settings.volume, 0.1 * direction, VOLUME_RANGE);
+            }
+            OptionItem::Fullscreen => settings.fullscreen = !settings.fullscreen,
+            OptionItem::Vsync => settings.vsync = !settings.vsync,
+            OptionItem::Palette => {
+                settings.palette = if direction > 0.0 {
+                    settings.palette.next()
+                } else {
+                    settings.palette.previous()
+                };
+            }
+            OptionItem::LeftKey
+            | OptionItem::RightKey
+            | OptionItem::ReleaseKey
+            | OptionItem::FireKey
+            | OptionItem::OptionsKey => {}
+        }
+    }
+
+    /// Binds the selected action to the button pressed in the event, if it is a press.
+    fn rebind(&mut self, settings: &mut Settings, event: &Event) -> bool {
+        let button = match *event {
+            Event::WindowEvent {
+                event:
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                state: ElementState::Pressed,
+                                virtual_keycode: Some(key),
+                                ..
+                            },
+                        ..
+                    },
+                ..
+            } => Button::Key(key),
+            Event::WindowEvent {
+                event:
+                    WindowEvent::MouseInput {
+                        state: ElementState::Pressed,
+                        button,
+                        ..
+                    },
+                ..
+            } => Button::Mouse(button),
+            _ => return false,
+        };
+
+        let keybinds = &mut settings.keybinds;
+        match (OPTION_ITEMS[self.selected], button) {
+            (OptionItem::LeftKey, button) => keybinds.left = button,
+            (OptionItem::RightKey, button) => keybinds.right = button,
+            (OptionItem::ReleaseKey, button) => keybinds.release = button,
+            (OptionItem::FireKey, button) => keybinds.fire = button,
+            (OptionItem::OptionsKey, Button::Key(key)) => keybinds.options = key,
+            // the menu can only be opened from the keyboard
+            (OptionItem::OptionsKey, _) => return false,
+            _ => {}
+        }
+        self.rebinding = false;
+        true
+    }
+}
+
+impl SimpleState for OptionsState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+
+        // pause the game underneath
+        {
+            let mut time = world.write_resource::<Time>();
+            self.time_scale = time.time_scale();
+            time.set_time_scale(0.0);
+        }
+
+        self.font = Some(world.read_resource::<Loader>().load(
+            "font/square.ttf",
+            TtfFormat,
+            (),
+            &world.read_resource(),
+        ));
+
+        self.add_line(world, 0);
+        for i in 0..OPTION_ITEMS.len() {
+            self.add_line(world, i + 2);
+        }
+        self.update_lines(world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+
+        world
+            .delete_entities(&self.ui)
+            .expect("Error deleting options");
+        self.ui.clear();
+
+        world
+            .write_resource::<Time>()
+            .set_time_scale(self.time_scale);
+
+        if let Err(err) = world.read_resource::<Settings>().save() {
+            error!("Error saving settings: {}", err);
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        let event = match event {
+            StateEvent::Window(event) => event,
+            _ => return Trans::None,
+        };
+        if is_close_requested(&event) {
+            save_game(data.world);
+            return Trans::Quit;
+        }
+
+        let world = data.world;
+
+        if self.rebinding {
+            let rebound = self.rebind(&mut world.write_resource::<Settings>(), &event);
+            if rebound {
+                self.update_lines(world);
+            }
+            return Trans::None;
+        }
+
+        let options_key = world.read_resource::<Settings>().keybinds.options;
+        if is_key_down(&event, options_key) || is_key_down(&event, VirtualKeyCode::Escape) {
+            return Trans::Pop;
+        }
+
+        let count = OPTION_ITEMS.len();
+        if is_key_down(&event, VirtualKeyCode::Up) {
+            self.selected = (self.selected + count - 1) % count;
+        } else if is_key_down(&event, VirtualKeyCode::Down) {
+            self.selected = (self.selected + 1) % count;
+        } else if is_key_down(&event, VirtualKeyCode::Left) {
+            self.change(world, -1.0);
+        } else if is_key_down(&event, VirtualKeyCode::Right) {
+            self.change(world, 1.0);
+        } else if is_key_down(&event, VirtualKeyCode::Return) {
+            match OPTION_ITEMS[self.selected] {
+                OptionItem::LeftKey
+                | OptionItem::RightKey
+                | OptionItem::ReleaseKey
+                | OptionItem::FireKey
+                | OptionItem::OptionsKey => {
+                    self.rebinding = true;
+                }
+                _ => self.change(world, 1.0),
+            }
+        } else {
+            return Trans::None;
+        }
+
+        self.update_lines(world);
+        Trans::None
+    }
+}
+
+fn option_name(item: OptionItem) -> &'static str {
+    match item {
+        OptionItem::MouseSensitivity => "Mouse sensitivity",
+        OptionItem::PaddleSmoothing => "Paddle smoothing",
+        OptionItem::Volume => "Volume",
+        OptionItem::Fullscreen => "Fullscreen",
+        OptionItem::Vsync => "Vsync",
+        OptionItem::Palette => "Palette",
+        OptionItem::LeftKey => "Move left",
+        OptionItem::RightKey => "Move right",
+        OptionItem::ReleaseKey => "Release ball",
+        OptionItem::FireKey => "Fire laser",
+        OptionItem::OptionsKey => "Options menu",
+    }
+}
+
+fn option_value(settings: &Settings, item: OptionItem) -> String {
+    let on_off = |on: bool| if on { "on" } else { "off" }.to_string();
+
+    match item {
+        OptionItem::MouseSensitivity => format!("{:.1}", settings.mouse_sensitivity),
+        OptionItem::PaddleSmoothing => format!("{:.2}s", settings.paddle_smoothing),
+        OptionItem::Volume => format!("{:.0}%", settings.volume * 100.0),
+        OptionItem::Fullscreen => on_off(settings.fullscreen),
+        OptionItem::Vsync => on_off(settings.vsync),
+        OptionItem::Palette => format!("{:?}", settings.palette),
+        OptionItem::LeftKey => button_name(settings.keybinds.left),
+        OptionItem::RightKey => button_name(settings.keybinds.right),
+        OptionItem::ReleaseKey => button_name(settings.keybinds.release),
+        OptionItem::FireKey => button_name(settings.keybinds.fire),
+        OptionItem::OptionsKey => format!("{:?}", settings.keybinds.options),
+    }
+}
+
+fn button_name(button: Button) -> String {
+    match button {
+        Button::Key(key) => format!("{:?}", key),
+        Button::Mouse(button) => format!("{:?} mouse button", button),
+        button => format!("{:?}", button),
+    }
+}
+
+fn step(value: f32, by: f32, range: (f32, f32)) -> f32 {
+    // round to the step so repeated steps don't drift
+    let stepped = ((value + by) / by.abs()).round() * by.abs();
+    clamp(stepped, range)
+}
+
+/// Keeps the value within the range, treating NaN as the bottom of it.
+fn clamp(value: f32, (min, max): (f32, f32)) -> f32 {
+    value.max(min).min(max)
+}
@@ -0,0 +1,136 @@
+use crate::{
+    components::{Ball, Paddle, Stuck},
+    config::GameplayConfig,
+    game::{BotRng, ARENA_HEIGHT, ARENA_WIDTH, BALL_RADIUS, PADDLE_HEIGHT},
+    systems::{Controller, PaddleInput},
+};
+use amethyst::{
+    core::{math::Vector2, timing::Time, transform::Transform},
+    derive::SystemDesc,
+    ecs::{Join, Read, ReadStorage, System, SystemData, Write},
+};
+use rand::Rng;
+
+/// Steers the paddle while the bot is in control. It heads for the point where the next ball to
+/// come down will reach the paddle, allowing for bounces off the side walls but not off anything
+/// else, and lets go of balls and fires lasers straight away.
+#[derive(Default, SystemDesc)]
+pub struct AutoplaySystem {
+    /// Time until the bot next looks at the balls.
+    cooldown: f32,
+}
+
+impl AutoplaySystem {
+    pub fn new() -> AutoplaySystem {
+        AutoplaySystem { cooldown: 0.0 }
+    }
+}
+
+impl<'s> System<'s> for AutoplaySystem {
+    type SystemData = (
+        ReadStorage<'s, Ball>,
+        ReadStorage<'s, Stuck>,
+        ReadStorage<'s, Paddle>,
+        ReadStorage<'s, Transform>,
+        Read<'s, GameplayConfig>,
+        Read<'s, Time>,
+        Read<'s, Controller>,
+        Write<'s, BotRng>,
+        Write<'s, PaddleInput>,
+    );
+
+    fn run(
+        &mut self,
+        (balls, stucks, paddles, transforms, config, time, controller, mut rand, mut input): Self::SystemData,
+    ) {
+        if *controller != Controller::Bot {
+            return;
+        }
+        input.release = true;
+        input.fire = true;
+
+        let (paddle, paddle_translation) = match (&paddles, &transforms).join().next() {
+            Some((paddle, transform)) => (paddle, *transform.translation()),
+            None => return,
+        };
+
+        // under acceleration control, hold a key until the paddle is roughly where it should be
+        let diff_x = input.target_x - paddle_translation.x;
+        input.direction = if diff_x.abs() > paddle.width / 4.0 {
+            diff_x.signum()
+        } else {
+            0.0
+        };
+
+        self.cooldown -= time.delta_seconds();
+        if self.cooldown > 0.0 {
+            return;
+        }
+        self.cooldown = config.autoplay.reaction_delay;
+
+        let landing_y = paddle_translation.y + PADDLE_HEIGHT / 2.0 + BALL_RADIUS;
+
+        let landing = (&balls, &transforms, !&stucks)
+            .join()
+            .filter_map(|(ball, transform, _)| {
+                let translation = transform.translation();
+                let position = Vector2::new(translation.x, translation.y);
+                predict_landing(position, ball.velocity, landing_y)
+            })
+            .filter(|(x, time)| x.is_finite() && time.is_finite())
+            .min_by(|(_, a), (_, b)| a.total_cmp(b));
+
+        input.target_x = match landing {
+            Some((x, _)) => {
+                let error = config.autoplay.prediction_error;
+                if error > 0.0 {
+                    x + rand.0.gen_range(-error, error)
+                } else {
+                    x
+                }
+            }
+            // nothing coming down, so wait in the middle
+            None => ARENA_WIDTH / 2.0,
+        };
+    }
+}
+
+/// Where along the arena a ball will be when it comes down to `landing_y`, and how many seconds
+/// it will take, bouncing off the side walls and the ceiling on the way.
+fn predict_landing(
+    position: Vector2<f32>,
+    velocity: Vector2<f32>,
+    landing_y: f32,
+) -> Option<(f32, f32)> {
+    let ceiling_y = ARENA_HEIGHT - BALL_RADIUS;
+    let distance_y = if velocity.y < 0.0 {
+        position.y - landing_y
+    } else if velocity.y > 0.0 {
+        (ceiling_y - position.y) + (ceiling_y - landing_y)
+    } else {
+        return None;
+    };
+    if distance_y < 0.0 {
+        // already past the paddle
+        return None;
+    }
+
+    let time = distance_y / velocity.y.abs();
+    let x = fold(
+        position.x + velocity.x * time,
+        BALL_RADIUS,
+        ARENA_WIDTH - BALL_RADIUS,
+    );
+    Some((x, time))
+}
+
+/// Brings `x` back between `min` and `max` as if it had bounced off of them.
+fn fold(x: f32, min: f32, max: f32) -> f32 {
+    let width = max - min;
+    let offset = (x - min).rem_euclid(2.0 * width);
+    if offset > width {
+        max - (offset - width)
+    } else {
+        min + offset
+    }
+}
@@ -1,7 +1,10 @@
 use crate::{
-    collision::CollisionContext,
-    components::{Ball, Contact, ContactEventData},
-    game::{BALL_MAX_ROTATION, BALL_MAX_SCALE, BALL_MAX_SPEED, BALL_MIN_SCALE},
+    collision::{ActiveContacts, CollisionContext},
+    components::{Ball, Brick, Paddle, PathFollower, Stuck},
+    config::GameplayConfig,
+    events::{ContactEventData, GameplayEvent},
+    game::GameRng,
+    physics::{bounce, deflect, enforce_min_angle, Surface},
 };
 #[allow(unused_imports)]
 use amethyst::core::alga::linear::Transformation;
@@ -12,9 +15,12 @@ use amethyst::{
         transform::Transform,
     },
     derive::SystemDesc,
-    ecs::{Join, Read, ReadStorage, System, SystemData, WriteStorage},
+    ecs::{Entities, Entity, Join, Read, ReadStorage, System, SystemData, Write, WriteStorage},
+    shrev::{EventChannel, ReaderId},
 };
-use rand::{thread_rng, Rng};
+use ncollide2d::{pipeline::CollisionObjectSlabHandle, world::CollisionWorld};
+use rand::Rng;
+use std::collections::HashMap;
 
 #[derive(SystemDesc)]
 pub struct BallMovementSystem;
@@ -37,63 +43,321 @@ impl<'s> System<'s> for BallMovementSystem {
     }
 }
 
+/// A surface a ball started touching, along with the event to raise if it bounces off of it.
+struct BallContact {
+    surface: Surface,
+    hit: Option<GameplayEvent>,
+}
+
 #[derive(SystemDesc)]
-pub struct BallCollisionSystem;
+#[system_desc(name(BallCollisionSystemDesc))]
+pub struct BallCollisionSystem {
+    #[system_desc(event_channel_reader)]
+    contact_reader: ReaderId<ContactEventData>,
+}
+
+impl BallCollisionSystem {
+    pub fn new(contact_reader: ReaderId<ContactEventData>) -> BallCollisionSystem {
+        BallCollisionSystem { contact_reader }
+    }
+}
 
 impl<'s> System<'s> for BallCollisionSystem {
     type SystemData = (
+        Entities<'s>,
         WriteStorage<'s, Ball>,
-        ReadStorage<'s, Contact>,
+        WriteStorage<'s, Transform>,
+        ReadStorage<'s, Stuck>,
+        ReadStorage<'s, Paddle>,
+        ReadStorage<'s, Brick>,
+        ReadStorage<'s, PathFollower>,
+        Read<'s, EventChannel<ContactEventData>>,
+        Read<'s, ActiveContacts>,
         Read<'s, CollisionContext>,
+        Read<'s, GameplayConfig>,
+        Write<'s, EventChannel<GameplayEvent>>,
+        Write<'s, GameRng>,
     );
 
-    fn run(&mut self, (mut balls, contacts, context): Self::SystemData) {
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut balls,
+            mut transforms,
+            stucks,
+            paddles,
+            bricks,
+            followers,
+            contact_events,
+            active_contacts,
+            context,
+            config,
+            mut gameplay_events,
+            mut rand,
+        ): Self::SystemData,
+    ) {
         let world = &context.world;
-        let mut rand = thread_rng();
+        let min_angle = config.min_bounce_angle.to_radians();
+        let mut contacts: Vec<(Entity, Vec<BallContact>)> = vec![];
 
-        for (ball, contact) in (&mut balls, &contacts).join() {
-            let mut contacted = false;
+        // Gather everything each ball started touching this frame...
+        for &data in contact_events.read(&mut self.contact_reader) {
+            if let ContactEventData::Started {
+                you_handle,
+                other_handle,
+                you,
+                other,
+            } = data
+            {
+                if !balls.contains(you) {
+                    continue;
+                }
 
-            for &data in contact.contacts.iter() {
-                if let ContactEventData::Started {
-                    you_handle,
-                    other_handle,
-                    ..
-                } = data
-                {
-                    if let Some((_, _, _, manifold)) =
-                        world.contact_pair(you_handle, other_handle, true)
-                    {
-                        let normal: Unit<Vector2<f32>> =
-                            manifold.deepest_contact().unwrap().contact.normal.clone();
-
-                        if ball.velocity.dot(&normal) > 0.0 {
-                            ball.velocity -= 2.0 * ball.velocity.dot(&normal) * *normal;
-
-                            // randomly adjust ball direction
-                            let diff = (ball.velocity.dot(&normal) / ball.velocity.norm())
-                                .asin()
-                                .abs()
-                                .min(BALL_MAX_ROTATION);
-                            let rot = rand.gen_range(-diff, diff);
-                            let rot_mat: Matrix3<f32> = Matrix3::new_rotation(rot);
-                            ball.velocity = rot_mat.transform_vector(&ball.velocity);
-
-                            contacted = true;
+                if let Some((normal, depth)) = contact_normal(world, you_handle, other_handle) {
+                    let hit = match other {
+                        None => Some(GameplayEvent::BallHitWall {
+                            ball: you,
+                            normal: *normal,
+                            side: context.side(other_handle),
+                        }),
+                        Some(other) if paddles.contains(other) => {
+                            Some(GameplayEvent::BallHitPaddle {
+                                ball: you,
+                                paddle: other,
+                            })
+                        }
+                        Some(other) if bricks.contains(other) => {
+                            Some(GameplayEvent::BallHitBrick {
+                                ball: you,
+                                brick: other,
+                            })
                         }
+                        Some(_) => None,
+                    };
+
+                    let contact = BallContact {
+                        surface: Surface {
+                            normal,
+                            depth,
+                            velocity: surface_velocity(&followers, other),
+                        },
+                        hit,
+                    };
+                    match contacts.iter_mut().find(|(entity, _)| *entity == you) {
+                        Some((_, ball_contacts)) => ball_contacts.push(contact),
+                        None => contacts.push((you, vec![contact])),
                     }
                 }
             }
+        }
 
-            if contacted {
-                println!("Contact!");
+        // ...and bounce them off of it, once per ball no matter how many surfaces it hit.
+        let mut contacted = vec![];
+        for (entity, ball_contacts) in contacts {
+            let ball = balls.get_mut(entity).unwrap();
 
-                // randomly increase ball speed
-                if ball.velocity.norm() < BALL_MAX_SPEED {
-                    ball.velocity =
-                        rand.gen_range(BALL_MIN_SCALE, BALL_MAX_SCALE) * ball.velocity.clone();
+            let approaching: Vec<&BallContact> = ball_contacts
+                .iter()
+                .filter(|contact| contact.surface.is_approached_by(ball.velocity))
+                .collect();
+            if approaching.is_empty() {
+                continue;
+            }
+
+            let surfaces: Vec<Surface> =
+                approaching.iter().map(|contact| contact.surface).collect();
+            let bounce = bounce(ball.velocity, &surfaces);
+
+            for contact in approaching.iter() {
+                if let Some(hit) = contact.hit {
+                    gameplay_events.single_write(hit);
                 }
             }
+            contacted.push(entity);
+
+            // randomly adjust ball direction, without turning it back into anything it touched
+            let rot = if bounce.max_rotation > 0.0 {
+                rand.rng
+                    .gen_range(-bounce.max_rotation, bounce.max_rotation)
+            } else {
+                0.0
+            };
+            ball.velocity = deflect(&bounce, rot, min_angle);
         }
+
+        // Push balls out of anything they are still overlapping, e.g. a paddle moving into them
+        // or a wall they were spawned in...
+        for (entity, ball, transform, _) in
+            (&*entities, &mut balls, &mut transforms, !&stucks).join()
+        {
+            for contact in active_contacts.get(entity) {
+                if let Some((normal, depth)) =
+                    contact_normal(world, contact.you_handle, contact.other_handle)
+                {
+                    if depth > 0.0 {
+                        transform.append_translation_xyz(-normal.x * depth, -normal.y * depth, 0.0);
+                    }
+
+                    let surface = Surface {
+                        normal,
+                        depth,
+                        velocity: surface_velocity(&followers, contact.other),
+                    };
+                    if !contacted.contains(&entity) && surface.is_approached_by(ball.velocity) {
+                        ball.velocity = deflect(&bounce(ball.velocity, &[surface]), 0.0, min_angle);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// What a ball has been bouncing off of since it last touched the paddle or a brick.
+#[derive(Default)]
+struct BallHistory {
+    elapsed: f32,
+    surfaces: Vec<CollisionObjectSlabHandle>,
+}
+
+impl BallHistory {
+    /// Whether the last `bounces` surfaces touched alternate between the same two.
+    fn is_oscillating(&self, bounces: usize) -> bool {
+        if bounces < 2 || self.surfaces.len() < bounces {
+            return false;
+        }
+
+        let recent = &self.surfaces[self.surfaces.len() - bounces..];
+        recent[0] != recent[1] && recent.windows(3).all(|window| window[0] == window[2])
+    }
+}
+
+/// Nudges balls that have gone too long without touching the paddle or a brick they can break,
+/// or that keep bouncing back and forth between the same two surfaces, so they can't get stuck
+/// in a loop.
+#[derive(SystemDesc)]
+#[system_desc(name(BallNudgeSystemDesc))]
+pub struct BallNudgeSystem {
+    #[system_desc(event_channel_reader)]
+    contact_reader: ReaderId<ContactEventData>,
+    #[system_desc(skip)]
+    histories: HashMap<Entity, BallHistory>,
+}
+
+impl BallNudgeSystem {
+    pub fn new(contact_reader: ReaderId<ContactEventData>) -> BallNudgeSystem {
+        BallNudgeSystem {
+            contact_reader,
+            histories: HashMap::new(),
+        }
+    }
+}
+
+impl<'s> System<'s> for BallNudgeSystem {
+    type SystemData = (
+        Entities<'s>,
+        WriteStorage<'s, Ball>,
+        ReadStorage<'s, Stuck>,
+        ReadStorage<'s, Paddle>,
+        ReadStorage<'s, Brick>,
+        Read<'s, EventChannel<ContactEventData>>,
+        Read<'s, GameplayConfig>,
+        Read<'s, Time>,
+        Write<'s, GameRng>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, mut balls, stucks, paddles, bricks, contact_events, config, time, mut rand): Self::SystemData,
+    ) {
+        for &data in contact_events.read(&mut self.contact_reader) {
+            if let ContactEventData::Started {
+                other_handle,
+                you,
+                other,
+                ..
+            } = data
+            {
+                if !balls.contains(you) {
+                    continue;
+                }
+
+                let history = self.histories.entry(you).or_default();
+                match other {
+                    // indestructible bricks can't be broken, so hitting them isn't progress
+                    Some(other)
+                        if paddles.contains(other)
+                            || bricks.get(other).map_or(false, |brick| brick.destructible) =>
+                    {
+                        *history = BallHistory::default();
+                    }
+                    _ => history.surfaces.push(other_handle),
+                }
+            }
+        }
+
+        self.histories
+            .retain(|&entity, _| entities.is_alive(entity) && balls.contains(entity));
+
+        let max_nudge = config.stuck_nudge_angle.to_radians();
+        let min_angle = config.min_bounce_angle.to_radians();
+        for (entity, ball, stuck) in (&*entities, &mut balls, stucks.maybe()).join() {
+            let history = self.histories.entry(entity).or_default();
+            if stuck.is_some() {
+                // balls held by the paddle are exactly where they should be
+                *history = BallHistory::default();
+                continue;
+            }
+
+            history.elapsed += time.delta_seconds();
+            if history.elapsed < config.stuck_timeout
+                && !history.is_oscillating(config.stuck_bounces)
+            {
+                continue;
+            }
+
+            let rot = if max_nudge > 0.0 {
+                let rot = rand.rng.gen_range(max_nudge / 2.0, max_nudge);
+                if rand.rng.gen_bool(0.5) {
+                    rot
+                } else {
+                    -rot
+                }
+            } else {
+                0.0
+            };
+            let rot_mat: Matrix3<f32> = Matrix3::new_rotation(rot);
+            ball.velocity = enforce_min_angle(rot_mat.transform_vector(&ball.velocity), min_angle);
+
+            *history = BallHistory::default();
+        }
+    }
+}
+
+/// How fast whatever a ball touched is moving, if it is moving at all.
+fn surface_velocity(
+    followers: &ReadStorage<'_, PathFollower>,
+    other: Option<Entity>,
+) -> Vector2<f32> {
+    other
+        .and_then(|other| followers.get(other))
+        .map_or_else(Vector2::zeros, |follower| follower.velocity)
+}
+
+/// Finds the normal of the deepest contact between two objects, pointing from `you` towards
+/// `other`, along with its penetration depth.
+fn contact_normal(
+    world: &CollisionWorld<f32, Option<Entity>>,
+    you_handle: CollisionObjectSlabHandle,
+    other_handle: CollisionObjectSlabHandle,
+) -> Option<(Unit<Vector2<f32>>, f32)> {
+    let (first_handle, _, _, manifold) = world.contact_pair(you_handle, other_handle, true)?;
+    let contact = &manifold.deepest_contact()?.contact;
+
+    // the manifold normal points away from whichever object the pair lists first
+    if first_handle == you_handle {
+        Some((contact.normal, contact.depth))
+    } else {
+        Some((-contact.normal, contact.depth))
     }
 }
@@ -1,34 +1,82 @@
-use crate::components::{Ball, BallDestroyer, Contact, ContactEventData};
+use crate::{
+    collision::CollisionContext,
+    components::{Ball, BallDestroyer, Collidable},
+    events::{ContactEventData, GameplayEvent},
+};
 use amethyst::{
     core::ecs::{Entities, Join, ReadStorage},
     derive::SystemDesc,
-    ecs::{System, SystemData},
+    ecs::{Read, System, SystemData, Write},
+    shrev::{EventChannel, ReaderId},
 };
 
 #[derive(SystemDesc)]
-pub struct BallDestroyerSystem;
+#[system_desc(name(BallDestroyerSystemDesc))]
+pub struct BallDestroyerSystem {
+    #[system_desc(event_channel_reader)]
+    contact_reader: ReaderId<ContactEventData>,
+}
+
+impl BallDestroyerSystem {
+    pub fn new(contact_reader: ReaderId<ContactEventData>) -> BallDestroyerSystem {
+        BallDestroyerSystem { contact_reader }
+    }
+}
 
 impl<'s> System<'s> for BallDestroyerSystem {
     type SystemData = (
         Entities<'s>,
         ReadStorage<'s, BallDestroyer>,
         ReadStorage<'s, Ball>,
-        ReadStorage<'s, Contact>,
+        ReadStorage<'s, Collidable>,
+        Read<'s, EventChannel<ContactEventData>>,
+        Write<'s, CollisionContext>,
+        Write<'s, EventChannel<GameplayEvent>>,
     );
 
-    fn run(&mut self, (entities, ball_destroyers, balls, contacts): Self::SystemData) {
-        for (_ball_destroyer, contact) in (&ball_destroyers, &contacts).join() {
-            for &data in contact.contacts.iter() {
-                if let ContactEventData::Started { other, .. } = data {
-                    if let Some(other) = other {
-                        if balls.contains(other) {
-                            println!("Removing ball...");
+    fn run(
+        &mut self,
+        (
+            entities,
+            ball_destroyers,
+            balls,
+            collidables,
+            contact_events,
+            mut context,
+            mut gameplay_events,
+        ): Self::SystemData,
+    ) {
+        let mut lost = vec![];
 
-                            entities.delete(other).expect("Error deleting ball");
-                        }
+        for &data in contact_events.read(&mut self.contact_reader) {
+            if let ContactEventData::Started { you, other, .. } = data {
+                if let Some(other) = other {
+                    if ball_destroyers.contains(you)
+                        && balls.contains(other)
+                        && !lost.contains(&other)
+                    {
+                        lost.push(other);
                     }
                 }
             }
         }
+
+        if lost.is_empty() {
+            return;
+        }
+
+        for &ball in lost.iter() {
+            if let Some(collidable) = collidables.get(ball) {
+                context.world.remove(&[collidable.handle]);
+            }
+            entities.delete(ball).expect("Error deleting ball");
+
+            gameplay_events.single_write(GameplayEvent::BallLost { ball });
+        }
+
+        let remaining = (&balls).join().count() - lost.len();
+        if remaining == 0 {
+            gameplay_events.single_write(GameplayEvent::LastBallLost);
+        }
     }
 }
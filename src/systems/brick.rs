@@ -0,0 +1,99 @@
+use crate::{
+    collision::CollisionContext,
+    components::{Ball, Brick, Collidable, Laser, PowerUpKind},
+    events::{ContactEventData, GameplayEvent},
+    game::{initialize_power_up, GameRng, GameSprites, POWER_UP_CHANCE},
+};
+use amethyst::{
+    core::{math::Vector2, transform::Transform},
+    derive::SystemDesc,
+    ecs::{Entities, LazyUpdate, Read, ReadStorage, System, SystemData, Write},
+    prelude::World,
+    shrev::{EventChannel, ReaderId},
+};
+use rand::Rng;
+
+#[derive(SystemDesc)]
+#[system_desc(name(BrickSystemDesc))]
+pub struct BrickSystem {
+    #[system_desc(event_channel_reader)]
+    contact_reader: ReaderId<ContactEventData>,
+}
+
+impl BrickSystem {
+    pub fn new(contact_reader: ReaderId<ContactEventData>) -> BrickSystem {
+        BrickSystem { contact_reader }
+    }
+}
+
+impl<'s> System<'s> for BrickSystem {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, Brick>,
+        ReadStorage<'s, Ball>,
+        ReadStorage<'s, Laser>,
+        ReadStorage<'s, Collidable>,
+        ReadStorage<'s, Transform>,
+        Read<'s, EventChannel<ContactEventData>>,
+        Write<'s, CollisionContext>,
+        Write<'s, EventChannel<GameplayEvent>>,
+        Write<'s, GameRng>,
+        Read<'s, LazyUpdate>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            bricks,
+            balls,
+            lasers,
+            collidables,
+            transforms,
+            contact_events,
+            mut context,
+            mut gameplay_events,
+            mut rand,
+            lazy,
+        ): Self::SystemData,
+    ) {
+        let mut destroyed = vec![];
+
+        for &data in contact_events.read(&mut self.contact_reader) {
+            if let ContactEventData::Started {
+                you,
+                other: Some(other),
+                ..
+            } = data
+            {
+                if bricks.get(you).map_or(false, |brick| brick.destructible)
+                    && (balls.contains(other) || lasers.contains(other))
+                    && !destroyed.contains(&you)
+                {
+                    destroyed.push(you);
+                }
+            }
+        }
+
+        for brick in destroyed {
+            let translation = transforms.get(brick).unwrap().translation();
+            let position = Vector2::new(translation.x, translation.y);
+
+            context
+                .world
+                .remove(&[collidables.get(brick).unwrap().handle]);
+            entities.delete(brick).expect("Error deleting brick");
+
+            gameplay_events.single_write(GameplayEvent::BrickDestroyed { brick, position });
+
+            if rand.rng.gen_bool(POWER_UP_CHANCE) {
+                let kind = PowerUpKind::random(&mut rand.rng);
+
+                lazy.exec_mut(move |world: &mut World| {
+                    let sprite_sheet = world.read_resource::<GameSprites>().sprite_sheet.clone();
+                    initialize_power_up(world, sprite_sheet, position, kind);
+                });
+            }
+        }
+    }
+}
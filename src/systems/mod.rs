@@ -1,9 +1,23 @@
+mod autoplay;
 mod ball;
 mod ball_destroyer;
+mod brick;
 mod paddle;
+mod path;
+mod portal;
+mod power_up;
+mod speed;
+mod timed;
 mod world;
 
-pub use ball::{BallCollisionSystem, BallMovementSystem};
-pub use ball_destroyer::BallDestroyerSystem;
-pub use paddle::PaddleSystem;
+pub use autoplay::AutoplaySystem;
+pub use ball::{BallCollisionSystemDesc, BallMovementSystem, BallNudgeSystemDesc};
+pub use ball_destroyer::BallDestroyerSystemDesc;
+pub use brick::BrickSystemDesc;
+pub use paddle::{Controller, PaddleInput, PaddleInputSystem, PaddleSystem};
+pub use path::PathSystem;
+pub use portal::PortalSystem;
+pub use power_up::{ActivePowerUps, LaserSystemDesc, PowerUpSystemDesc, StuckBallSystemDesc};
+pub use speed::SpeedSystemDesc;
+pub use timed::{SystemTimings, Timed, TimedDesc};
 pub use world::WorldUpdateSystem;
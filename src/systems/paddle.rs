@@ -1,22 +1,92 @@
 use crate::{
     components::Paddle,
-    game::{ARENA_WIDTH, PADDLE_DISTANCE_VELOCITY_RATIO, PADDLE_MAX_VELOCITY, PADDLE_WIDTH},
+    config::{GameplayConfig, PaddleControl},
+    game::ARENA_WIDTH,
+    settings::Settings,
 };
 use amethyst::{
     core::{timing::Time, transform::Transform},
     derive::SystemDesc,
-    ecs::{Join, Read, ReadStorage, System, SystemData, WriteStorage},
+    ecs::{Join, Read, ReadStorage, System, SystemData, Write, WriteStorage},
     input::{InputHandler, StringBindings},
 };
+use serde::{Deserialize, Serialize};
 
-#[derive(SystemDesc)]
+/// Who is steering the paddle.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Controller {
+    /// The mouse and keyboard.
+    Player,
+    /// The `AutoplaySystem`.
+    Bot,
+    /// Something outside of the systems, like a replay or a training agent, which sets the
+    /// `PaddleInput` itself.
+    External,
+}
+
+impl Default for Controller {
+    fn default() -> Controller {
+        Controller::Player
+    }
+}
+
+/// What the paddle is being asked to do this frame, whoever is asking.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PaddleInput {
+    /// Where the paddle should head for under instant and proportional control.
+    pub target_x: f32,
+    /// Which way to push the paddle under acceleration control: -1, 0 or 1.
+    pub direction: f32,
+    /// Let go of balls held on a sticky paddle.
+    pub release: bool,
+    pub fire: bool,
+}
+
+/// Turns the mouse and keys into the `PaddleInput` while the player is in control.
+#[derive(Default, SystemDesc)]
+pub struct PaddleInputSystem;
+
+impl<'s> System<'s> for PaddleInputSystem {
+    type SystemData = (
+        Read<'s, InputHandler<StringBindings>>,
+        Read<'s, Settings>,
+        Read<'s, Controller>,
+        Write<'s, PaddleInput>,
+    );
+
+    fn run(&mut self, (input, settings, controller, mut paddle_input): Self::SystemData) {
+        if *controller != Controller::Player {
+            return;
+        }
+
+        if let Some((x, _y)) = input.mouse_position() {
+            let center_x = ARENA_WIDTH / 2.0;
+            paddle_input.target_x = center_x + (x - center_x) * settings.mouse_sensitivity;
+        }
+
+        let keybinds = &settings.keybinds;
+        paddle_input.direction = 0.0;
+        if input.button_is_down(keybinds.left) {
+            paddle_input.direction -= 1.0;
+        }
+        if input.button_is_down(keybinds.right) {
+            paddle_input.direction += 1.0;
+        }
+
+        paddle_input.release = input.button_is_down(keybinds.release);
+        paddle_input.fire = input.button_is_down(keybinds.fire);
+    }
+}
+
+#[derive(Default, SystemDesc)]
 pub struct PaddleSystem {
-    target_x: f32,
+    /// Only kept between frames by the acceleration control.
+    velocity: f32,
 }
 
 impl PaddleSystem {
     pub fn new() -> PaddleSystem {
-        PaddleSystem { target_x: 0.0 }
+        PaddleSystem { velocity: 0.0 }
     }
 }
 
@@ -24,38 +94,78 @@ impl<'s> System<'s> for PaddleSystem {
     type SystemData = (
         WriteStorage<'s, Transform>,
         ReadStorage<'s, Paddle>,
-        Read<'s, InputHandler<StringBindings>>,
+        Read<'s, PaddleInput>,
+        Read<'s, GameplayConfig>,
+        Read<'s, Settings>,
         Read<'s, Time>,
     );
 
-    fn run(&mut self, (mut transforms, paddles, input, time): Self::SystemData) {
-        if let Some((x, _y)) = input.mouse_position() {
-            if x < PADDLE_WIDTH / 2.0 {
-                self.target_x = PADDLE_WIDTH / 2.0;
-            } else if x > ARENA_WIDTH - PADDLE_WIDTH / 2.0 {
-                self.target_x = ARENA_WIDTH - PADDLE_WIDTH / 2.0;
-            } else {
-                self.target_x = x;
-            }
-        }
-        for (_paddle, transform) in (&paddles, &mut transforms).join() {
+    fn run(&mut self, (mut transforms, paddles, input, config, settings, time): Self::SystemData) {
+        let delta_seconds = time.delta_seconds();
+
+        for (paddle, transform) in (&paddles, &mut transforms).join() {
+            let (min_x, max_x) = paddle.bounds(ARENA_WIDTH);
             let paddle_x = transform.translation().x;
-            let diff_x = self.target_x - paddle_x;
-            let abs_diff_x = diff_x.abs();
-            let vel_x = if abs_diff_x > 0.0 {
-                if abs_diff_x > PADDLE_MAX_VELOCITY * PADDLE_DISTANCE_VELOCITY_RATIO {
-                    if diff_x > 0.0 {
-                        PADDLE_MAX_VELOCITY
-                    } else {
-                        -PADDLE_MAX_VELOCITY
-                    }
-                } else {
-                    diff_x / PADDLE_DISTANCE_VELOCITY_RATIO
+
+            let new_x = match config.paddle_control {
+                PaddleControl::Instant => input.target_x,
+                PaddleControl::Proportional { max_velocity } => {
+                    let target_x = input.target_x.max(min_x).min(max_x);
+                    let vel_x = proportional_velocity(
+                        target_x - paddle_x,
+                        settings.paddle_smoothing,
+                        max_velocity,
+                    );
+                    paddle_x + vel_x * delta_seconds
+                }
+                PaddleControl::Acceleration {
+                    acceleration,
+                    friction,
+                    max_velocity,
+                } => {
+                    self.velocity = accelerate(
+                        self.velocity,
+                        input.direction,
+                        acceleration,
+                        friction,
+                        delta_seconds,
+                    )
+                    .max(-max_velocity)
+                    .min(max_velocity);
+                    paddle_x + self.velocity * delta_seconds
                 }
-            } else {
-                0.0
             };
-            transform.set_translation_x(paddle_x + vel_x * time.delta_seconds());
+
+            if new_x <= min_x || new_x >= max_x {
+                // ran into the side of the arena
+                self.velocity = 0.0;
+            }
+            transform.set_translation_x(new_x.max(min_x).min(max_x));
         }
     }
 }
+
+/// Covers the distance in `smoothing` seconds, unless that would be faster than `max_velocity`.
+fn proportional_velocity(diff_x: f32, smoothing: f32, max_velocity: f32) -> f32 {
+    if diff_x.abs() > max_velocity * smoothing {
+        max_velocity.copysign(diff_x)
+    } else {
+        diff_x / smoothing
+    }
+}
+
+/// Speeds up in the direction held, or slows to a stop when nothing is held.
+fn accelerate(
+    velocity: f32,
+    direction: f32,
+    acceleration: f32,
+    friction: f32,
+    delta_seconds: f32,
+) -> f32 {
+    if direction != 0.0 {
+        velocity + direction * acceleration * delta_seconds
+    } else {
+        let slowed = velocity.abs() - friction * delta_seconds;
+        slowed.max(0.0).copysign(velocity)
+    }
+}
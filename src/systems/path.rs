@@ -0,0 +1,40 @@
+use crate::components::PathFollower;
+use amethyst::{
+    core::{math::Vector2, timing::Time, transform::Transform},
+    derive::SystemDesc,
+    ecs::{Join, Read, System, SystemData, WriteStorage},
+};
+
+/// Moves everything with a `PathFollower` along its path, keeping track of how fast it went.
+#[derive(SystemDesc)]
+pub struct PathSystem;
+
+impl<'s> System<'s> for PathSystem {
+    type SystemData = (
+        WriteStorage<'s, PathFollower>,
+        WriteStorage<'s, Transform>,
+        Read<'s, Time>,
+    );
+
+    fn run(&mut self, (mut followers, mut transforms, time): Self::SystemData) {
+        let delta = time.delta_seconds();
+
+        for (follower, transform) in (&mut followers, &mut transforms).join() {
+            follower.elapsed += delta;
+
+            let translation = transform.translation();
+            let previous = Vector2::new(translation.x, translation.y);
+            let position = follower.origin + follower.path.offset(follower.elapsed);
+
+            follower.velocity = if delta > 0.0 {
+                (position - previous) / delta
+            } else {
+                Vector2::zeros()
+            };
+
+            transform.set_translation_x(position.x);
+            transform.set_translation_y(position.y);
+            transform.set_rotation_2d(follower.spin * follower.elapsed);
+        }
+    }
+}
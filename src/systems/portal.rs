@@ -0,0 +1,44 @@
+use crate::{
+    collision::CollisionContext,
+    components::{Ball, Stuck},
+};
+use amethyst::{
+    core::transform::Transform,
+    derive::SystemDesc,
+    ecs::{Join, Read, ReadStorage, System, SystemData, WriteStorage},
+};
+
+/// Moves balls that enter one of the arena's portals out of its exit.
+#[derive(SystemDesc)]
+pub struct PortalSystem;
+
+impl<'s> System<'s> for PortalSystem {
+    type SystemData = (
+        ReadStorage<'s, Ball>,
+        ReadStorage<'s, Stuck>,
+        WriteStorage<'s, Transform>,
+        Read<'s, CollisionContext>,
+    );
+
+    fn run(&mut self, (balls, stucks, mut transforms, context): Self::SystemData) {
+        let portals = &context.arena.portals;
+        if portals.is_empty() {
+            return;
+        }
+
+        for (_, transform, _) in (&balls, &mut transforms, !&stucks).join() {
+            let translation = transform.translation();
+            let (x, y) = (translation.x, translation.y);
+
+            let portal = portals.iter().find(|portal| {
+                let (dx, dy) = (x - portal.entrance.0, y - portal.entrance.1);
+                dx * dx + dy * dy < portal.radius * portal.radius
+            });
+
+            if let Some(portal) = portal {
+                transform.set_translation_x(portal.exit.0);
+                transform.set_translation_y(portal.exit.1);
+            }
+        }
+    }
+}
@@ -0,0 +1,428 @@
+use crate::{
+    collision::CollisionContext,
+    components::{Ball, BallDestroyer, Collidable, Laser, Paddle, PowerUp, PowerUpKind, Stuck},
+    events::ContactEventData,
+    game::{
+        initialize_ball, initialize_laser, GameSprites, BALL_MAX_RELEASE_ANGLE, BALL_RADIUS,
+        LASER_COOLDOWN, LASER_HEIGHT, LASER_SPEED, LASER_WIDTH, MULTI_BALL_COUNT,
+        MULTI_BALL_SPREAD, PADDLE_HEIGHT, PADDLE_WIDTH, POWER_UP_DURATION, POWER_UP_FALL_SPEED,
+        SLOW_BALL_MAX_SPEED, WIDE_PADDLE_SCALE,
+    },
+    physics::limit_speed,
+    systems::PaddleInput,
+};
+use amethyst::{
+    core::{
+        math::{Rotation2, Vector2},
+        timing::Time,
+        transform::Transform,
+    },
+    derive::SystemDesc,
+    ecs::{
+        Entities, Entity, Join, LazyUpdate, Read, ReadStorage, System, SystemData, Write,
+        WriteStorage,
+    },
+    prelude::World,
+    shrev::{EventChannel, ReaderId},
+};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Remaining time in seconds of every timed power-up currently in effect.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ActivePowerUps {
+    timers: HashMap<PowerUpKind, f32>,
+}
+
+impl ActivePowerUps {
+    pub fn is_active(&self, kind: PowerUpKind) -> bool {
+        self.timers.contains_key(&kind)
+    }
+
+    fn activate(&mut self, kind: PowerUpKind) {
+        self.timers.insert(kind, POWER_UP_DURATION);
+    }
+
+    /// Counts down all timers, returning the power-ups that just ran out.
+    fn tick(&mut self, delta: f32) -> Vec<PowerUpKind> {
+        let mut expired = vec![];
+
+        for (&kind, remaining) in self.timers.iter_mut() {
+            *remaining -= delta;
+            if *remaining <= 0.0 {
+                expired.push(kind);
+            }
+        }
+
+        for kind in expired.iter() {
+            self.timers.remove(kind);
+        }
+
+        expired
+    }
+}
+
+#[derive(SystemDesc)]
+#[system_desc(name(PowerUpSystemDesc))]
+pub struct PowerUpSystem {
+    #[system_desc(event_channel_reader)]
+    contact_reader: ReaderId<ContactEventData>,
+}
+
+impl PowerUpSystem {
+    pub fn new(contact_reader: ReaderId<ContactEventData>) -> PowerUpSystem {
+        PowerUpSystem { contact_reader }
+    }
+}
+
+impl<'s> System<'s> for PowerUpSystem {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, PowerUp>,
+        ReadStorage<'s, BallDestroyer>,
+        ReadStorage<'s, Collidable>,
+        ReadStorage<'s, Stuck>,
+        WriteStorage<'s, Paddle>,
+        WriteStorage<'s, Ball>,
+        WriteStorage<'s, Transform>,
+        Read<'s, EventChannel<ContactEventData>>,
+        Write<'s, ActivePowerUps>,
+        Write<'s, CollisionContext>,
+        Read<'s, LazyUpdate>,
+        Read<'s, Time>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            power_ups,
+            ball_destroyers,
+            collidables,
+            stucks,
+            mut paddles,
+            mut balls,
+            mut transforms,
+            contact_events,
+            mut active,
+            mut context,
+            lazy,
+            time,
+        ): Self::SystemData,
+    ) {
+        // Let the power-ups fall...
+        for (_power_up, transform) in (&power_ups, &mut transforms).join() {
+            transform.append_translation_xyz(0.0, -POWER_UP_FALL_SPEED * time.delta_seconds(), 0.0);
+        }
+
+        // Collect or discard the ones that hit something, other than bricks...
+        let mut removed = vec![];
+        let mut collected = vec![];
+        for &data in contact_events.read(&mut self.contact_reader) {
+            if let ContactEventData::Started { you, other, .. } = data {
+                if let Some(power_up) = power_ups.get(you) {
+                    if removed.contains(&you) {
+                        continue;
+                    }
+
+                    match other {
+                        Some(other) if paddles.contains(other) => collected.push(power_up.kind),
+                        Some(other) if !ball_destroyers.contains(other) => continue,
+                        // a lethal edge, or one of the arena's walls
+                        _ => {}
+                    }
+
+                    removed.push(you);
+                }
+            }
+        }
+
+        for power_up in removed {
+            context
+                .world
+                .remove(&[collidables.get(power_up).unwrap().handle]);
+            entities.delete(power_up).expect("Error deleting power-up");
+        }
+
+        for kind in collected {
+            info!("Power-up: {:?}", kind);
+
+            match kind {
+                PowerUpKind::MultiBall => {
+                    spawn_multi_ball(&balls, &stucks, &transforms, &lazy);
+                }
+                PowerUpKind::WidePaddle => {
+                    set_paddle_width(
+                        PADDLE_WIDTH * WIDE_PADDLE_SCALE,
+                        &mut paddles,
+                        &collidables,
+                        &mut transforms,
+                        &mut context,
+                    );
+                    active.activate(kind);
+                }
+                _ => active.activate(kind),
+            }
+        }
+
+        for kind in active.tick(time.delta_seconds()) {
+            if let PowerUpKind::WidePaddle = kind {
+                set_paddle_width(
+                    PADDLE_WIDTH,
+                    &mut paddles,
+                    &collidables,
+                    &mut transforms,
+                    &mut context,
+                );
+            }
+        }
+
+        if active.is_active(PowerUpKind::SlowBall) {
+            for ball in (&mut balls).join() {
+                ball.velocity = limit_speed(ball.velocity, SLOW_BALL_MAX_SPEED);
+            }
+        }
+    }
+}
+
+/// Splits one of the balls in play into several, fanned out around its direction. Balls held on
+/// the paddle aren't moving, so they are left out.
+fn spawn_multi_ball(
+    balls: &WriteStorage<'_, Ball>,
+    stucks: &ReadStorage<'_, Stuck>,
+    transforms: &WriteStorage<'_, Transform>,
+    lazy: &LazyUpdate,
+) {
+    let (ball, transform) = match (balls, !stucks, transforms)
+        .join()
+        .find(|(ball, _, _)| ball.velocity.norm() > 0.0)
+    {
+        Some((ball, _, transform)) => (ball, transform),
+        None => return,
+    };
+    let translation = transform.translation();
+    let position = Vector2::new(translation.x, translation.y);
+
+    for i in 0..MULTI_BALL_COUNT {
+        let side = if i % 2 == 0 { 1.0 } else { -1.0 };
+        let angle = side * MULTI_BALL_SPREAD * (i / 2 + 1) as f32;
+        let velocity = Rotation2::new(angle) * ball.velocity;
+
+        lazy.exec_mut(move |world: &mut World| {
+            let sprite_sheet = world.read_resource::<GameSprites>().sprite_sheet.clone();
+            initialize_ball(world, sprite_sheet, position, velocity);
+        });
+    }
+}
+
+fn set_paddle_width(
+    width: f32,
+    paddles: &mut WriteStorage<'_, Paddle>,
+    collidables: &ReadStorage<'_, Collidable>,
+    transforms: &mut WriteStorage<'_, Transform>,
+    context: &mut CollisionContext,
+) {
+    for (paddle, collidable, transform) in (paddles, collidables, transforms).join() {
+        paddle.set_width(width, transform, collidable, context);
+    }
+}
+
+#[derive(SystemDesc)]
+#[system_desc(name(StuckBallSystemDesc))]
+pub struct StuckBallSystem {
+    #[system_desc(event_channel_reader)]
+    contact_reader: ReaderId<ContactEventData>,
+}
+
+impl StuckBallSystem {
+    pub fn new(contact_reader: ReaderId<ContactEventData>) -> StuckBallSystem {
+        StuckBallSystem { contact_reader }
+    }
+}
+
+impl<'s> System<'s> for StuckBallSystem {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, Paddle>,
+        WriteStorage<'s, Ball>,
+        WriteStorage<'s, Stuck>,
+        WriteStorage<'s, Transform>,
+        Read<'s, EventChannel<ContactEventData>>,
+        Read<'s, ActivePowerUps>,
+        Read<'s, PaddleInput>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            paddles,
+            mut balls,
+            mut stucks,
+            mut transforms,
+            contact_events,
+            active,
+            input,
+        ): Self::SystemData,
+    ) {
+        let sticky = active.is_active(PowerUpKind::StickyPaddle);
+
+        // Catch balls that just landed on the paddle...
+        for &data in contact_events.read(&mut self.contact_reader) {
+            if let ContactEventData::Started {
+                you,
+                other: Some(other),
+                ..
+            } = data
+            {
+                if !sticky || !paddles.contains(other) || stucks.contains(you) {
+                    continue;
+                }
+
+                if let Some(ball) = balls.get_mut(you) {
+                    let offset = transforms.get(you).unwrap().translation().x
+                        - transforms.get(other).unwrap().translation().x;
+                    let speed = ball.velocity.norm();
+                    ball.velocity = Vector2::zeros();
+
+                    stucks
+                        .insert(you, Stuck { offset, speed })
+                        .expect("Error sticking ball");
+                }
+            }
+        }
+
+        // ...and carry them along until they are released.
+        let paddle = (&paddles, &transforms)
+            .join()
+            .next()
+            .map(|(paddle, transform)| (paddle.width, *transform.translation()));
+        let (paddle_width, paddle_translation) = match paddle {
+            Some(paddle) => paddle,
+            None => return,
+        };
+
+        let release = !sticky || input.release;
+        let mut released: Vec<Entity> = vec![];
+
+        for (entity, ball, stuck, transform) in
+            (&*entities, &mut balls, &stucks, &mut transforms).join()
+        {
+            transform.set_translation_xyz(
+                paddle_translation.x + stuck.offset,
+                paddle_translation.y + PADDLE_HEIGHT / 2.0 + BALL_RADIUS,
+                0.0,
+            );
+
+            if release {
+                let angle = (stuck.offset / (paddle_width / 2.0)).max(-1.0).min(1.0)
+                    * BALL_MAX_RELEASE_ANGLE;
+                ball.velocity = Vector2::new(angle.sin(), angle.cos()) * stuck.speed;
+                released.push(entity);
+            }
+        }
+
+        for entity in released {
+            stucks.remove(entity);
+        }
+    }
+}
+
+#[derive(SystemDesc)]
+#[system_desc(name(LaserSystemDesc))]
+pub struct LaserSystem {
+    #[system_desc(event_channel_reader)]
+    contact_reader: ReaderId<ContactEventData>,
+    #[system_desc(skip)]
+    cooldown: f32,
+}
+
+impl LaserSystem {
+    pub fn new(contact_reader: ReaderId<ContactEventData>) -> LaserSystem {
+        LaserSystem {
+            contact_reader,
+            cooldown: 0.0,
+        }
+    }
+}
+
+impl<'s> System<'s> for LaserSystem {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, Laser>,
+        ReadStorage<'s, Paddle>,
+        ReadStorage<'s, Collidable>,
+        WriteStorage<'s, Transform>,
+        Read<'s, EventChannel<ContactEventData>>,
+        Read<'s, ActivePowerUps>,
+        Read<'s, PaddleInput>,
+        Read<'s, Time>,
+        Read<'s, LazyUpdate>,
+        Write<'s, CollisionContext>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            lasers,
+            paddles,
+            collidables,
+            mut transforms,
+            contact_events,
+            active,
+            input,
+            time,
+            lazy,
+            mut context,
+        ): Self::SystemData,
+    ) {
+        // Remove the lasers that hit something...
+        let mut hit = vec![];
+        for &data in contact_events.read(&mut self.contact_reader) {
+            if let ContactEventData::Started { you, other, .. } = data {
+                if lasers.contains(you)
+                    && other.map_or(true, |other| !paddles.contains(other))
+                    && !hit.contains(&you)
+                {
+                    hit.push(you);
+                }
+            }
+        }
+
+        for laser in hit {
+            context
+                .world
+                .remove(&[collidables.get(laser).unwrap().handle]);
+            entities.delete(laser).expect("Error deleting laser");
+        }
+
+        // ...move the rest...
+        for (_laser, transform) in (&lasers, &mut transforms).join() {
+            transform.append_translation_xyz(0.0, LASER_SPEED * time.delta_seconds(), 0.0);
+        }
+
+        // ...and fire new ones.
+        self.cooldown -= time.delta_seconds();
+        if active.is_active(PowerUpKind::Laser) && self.cooldown <= 0.0 && input.fire {
+            self.cooldown = LASER_COOLDOWN;
+
+            for (paddle, transform) in (&paddles, &transforms).join() {
+                let translation = transform.translation();
+                let y = translation.y + PADDLE_HEIGHT / 2.0 + LASER_HEIGHT / 2.0;
+
+                for &side in [-1.0f32, 1.0].iter() {
+                    let position =
+                        Vector2::new(translation.x + side * (paddle.width / 2.0 - LASER_WIDTH), y);
+
+                    lazy.exec_mut(move |world: &mut World| {
+                        let sprite_sheet =
+                            world.read_resource::<GameSprites>().sprite_sheet.clone();
+                        initialize_laser(world, sprite_sheet, position);
+                    });
+                }
+            }
+        }
+    }
+}
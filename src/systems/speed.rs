@@ -0,0 +1,90 @@
+use crate::{
+    components::{Ball, Brick},
+    config::{GameplayConfig, SpeedProgress},
+    events::GameplayEvent,
+    level::{Campaign, Side},
+    physics::set_speed,
+};
+use amethyst::{
+    core::timing::Time,
+    derive::SystemDesc,
+    ecs::{Join, Read, ReadStorage, System, SystemData, Write, WriteStorage},
+    shrev::{EventChannel, ReaderId},
+};
+
+/// Keeps every ball moving at the speed the configured `SpeedPolicy` calls for, unless the
+/// current level of the campaign fixes its own ball speed.
+#[derive(SystemDesc)]
+#[system_desc(name(SpeedSystemDesc))]
+pub struct SpeedSystem {
+    #[system_desc(event_channel_reader)]
+    gameplay_reader: ReaderId<GameplayEvent>,
+}
+
+impl SpeedSystem {
+    pub fn new(gameplay_reader: ReaderId<GameplayEvent>) -> SpeedSystem {
+        SpeedSystem { gameplay_reader }
+    }
+}
+
+impl<'s> System<'s> for SpeedSystem {
+    type SystemData = (
+        WriteStorage<'s, Ball>,
+        ReadStorage<'s, Brick>,
+        Read<'s, EventChannel<GameplayEvent>>,
+        Read<'s, GameplayConfig>,
+        Read<'s, Campaign>,
+        Write<'s, SpeedProgress>,
+        Read<'s, Time>,
+    );
+
+    fn run(
+        &mut self,
+        (mut balls, bricks, gameplay_events, config, campaign, mut progress, time): Self::SystemData,
+    ) {
+        progress.elapsed += time.delta_seconds();
+
+        for event in gameplay_events.read(&mut self.gameplay_reader) {
+            match *event {
+                GameplayEvent::BallHitPaddle { .. } => progress.hits += 1,
+                GameplayEvent::BallHitBrick { brick, .. } => {
+                    progress.hits += 1;
+
+                    if let Some(brick) = bricks.get(brick) {
+                        progress.highest_row = Some(
+                            progress
+                                .highest_row
+                                .map_or(brick.row, |highest_row| highest_row.min(brick.row)),
+                        );
+                    }
+                }
+                GameplayEvent::BallHitWall {
+                    side: Some(Side::Top),
+                    ..
+                } => {
+                    progress.ceiling_hit = true;
+                }
+                GameplayEvent::LastBallLost => {
+                    *progress = SpeedProgress {
+                        level: progress.level,
+                        ..SpeedProgress::default()
+                    };
+                }
+                _ => {}
+            }
+        }
+
+        let speed = match campaign
+            .ball_speed(progress.level)
+            .or_else(|| config.speed_policy.speed(&progress))
+        {
+            Some(speed) => speed,
+            None => return,
+        };
+
+        for ball in (&mut balls).join() {
+            // balls held on the paddle aren't moving at all, and stay that way
+            ball.velocity = set_speed(ball.velocity, speed);
+        }
+    }
+}
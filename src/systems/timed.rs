@@ -0,0 +1,99 @@
+use amethyst::{
+    core::SystemDesc,
+    ecs::{System, World},
+};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// How long each timed system has spent running, by name.
+#[derive(Default)]
+pub struct SystemTimings {
+    timings: HashMap<&'static str, Arc<Mutex<Duration>>>,
+}
+
+impl SystemTimings {
+    /// The total time the named system has spent running, or zero if there is no timed system
+    /// by that name.
+    pub fn get(&self, name: &str) -> Duration {
+        self.timings
+            .get(name)
+            .map_or_else(Duration::default, |timing| *timing.lock().unwrap())
+    }
+
+    /// Starts every total over from zero.
+    pub fn reset(&self) {
+        for timing in self.timings.values() {
+            *timing.lock().unwrap() = Duration::default();
+        }
+    }
+}
+
+/// Runs another system, adding up how long it takes in the `SystemTimings`.
+pub struct Timed<S> {
+    name: &'static str,
+    system: S,
+    elapsed: Arc<Mutex<Duration>>,
+}
+
+impl<S> Timed<S> {
+    pub fn new(name: &'static str, system: S) -> Timed<S> {
+        Timed {
+            name,
+            system,
+            elapsed: Arc::default(),
+        }
+    }
+}
+
+impl<'s, S: System<'s>> System<'s> for Timed<S> {
+    type SystemData = S::SystemData;
+
+    fn run(&mut self, data: Self::SystemData) {
+        let start = Instant::now();
+        self.system.run(data);
+        *self.elapsed.lock().unwrap() += start.elapsed();
+    }
+
+    fn setup(&mut self, world: &mut World) {
+        self.system.setup(world);
+        world
+            .entry::<SystemTimings>()
+            .or_insert_with(SystemTimings::default)
+            .timings
+            .insert(self.name, self.elapsed.clone());
+    }
+}
+
+/// Builds a system from its `SystemDesc` and times it.
+pub struct TimedDesc<D> {
+    name: &'static str,
+    desc: D,
+}
+
+impl<D> TimedDesc<D> {
+    pub fn new(name: &'static str, desc: D) -> TimedDesc<D> {
+        TimedDesc { name, desc }
+    }
+}
+
+impl<D> fmt::Debug for TimedDesc<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TimedDesc")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+impl<'a, 'b, D, S> SystemDesc<'a, 'b, Timed<S>> for TimedDesc<D>
+where
+    D: SystemDesc<'a, 'b, S>,
+    S: for<'c> System<'c> + Send + 'a,
+{
+    fn build(self, world: &mut World) -> Timed<S> {
+        Timed::new(self.name, self.desc.build(world))
+    }
+}
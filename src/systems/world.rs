@@ -1,6 +1,7 @@
 use crate::{
-    collision::CollisionContext,
-    components::{Collidable, Contact, ContactEventData},
+    collision::{ActiveContacts, CollisionContext},
+    components::Collidable,
+    events::ContactEventData,
 };
 use amethyst::{
     core::{
@@ -8,95 +9,78 @@ use amethyst::{
         transform::Transform,
     },
     derive::SystemDesc,
-    ecs::{Entities, Entity, Join, ReadStorage, System, SystemData, Write, WriteStorage},
+    ecs::{Entities, Entity, Join, ReadStorage, System, SystemData, Write},
+    shrev::EventChannel,
 };
 use ncollide2d::{narrow_phase::ContactEvent, pipeline::CollisionObjectSlabHandle};
-use smallvec::smallvec;
-use std::collections::HashMap;
 
 #[derive(SystemDesc, Default)]
-pub struct WorldUpdateSystem {
-    entities: HashMap<CollisionObjectSlabHandle, Entity>,
-}
+pub struct WorldUpdateSystem;
 
 impl<'s> System<'s> for WorldUpdateSystem {
     type SystemData = (
         Entities<'s>,
         ReadStorage<'s, Transform>,
         ReadStorage<'s, Collidable>,
-        WriteStorage<'s, Contact>,
         Write<'s, CollisionContext>,
+        Write<'s, EventChannel<ContactEventData>>,
+        Write<'s, ActiveContacts>,
     );
 
     fn run(
         &mut self,
-        (entities, transforms, collidables, mut contacts, mut context): Self::SystemData,
+        (
+            entities,
+            transforms,
+            collidables,
+            mut context,
+            mut contact_events,
+            mut active_contacts,
+        ): Self::SystemData,
     ) {
-        let world = &mut context.world;
-
         // Update positions...
         for (transform, collidable) in (&transforms, &collidables).join() {
             let translation = transform.translation();
-            let position = Isometry2::new(Vector2::new(translation.x, translation.y), 0.0);
-            let obj = world.get_mut(collidable.handle).unwrap();
+            let (_, _, angle) = transform.rotation().euler_angles();
+            let position = Isometry2::new(Vector2::new(translation.x, translation.y), angle);
+            let obj = context.world.get_mut(collidable.handle).unwrap();
             obj.set_position(position);
         }
 
         // Update the world...
-        world.update();
+        context.world.update();
 
-        // Update contact components...
-        for (entity, collidable) in (&*entities, &collidables).join() {
-            self.entities.insert(collidable.handle, entity);
-            contacts.remove(entity);
-        }
+        let context = &*context;
+        let world = &context.world;
+
+        // Forget contacts with objects that have been removed...
+        active_contacts.retain(|entity, contact| {
+            entities.is_alive(entity)
+                && world
+                    .contact_pair(contact.you_handle, contact.other_handle, true)
+                    .is_some()
+        });
 
+        // Publish contact events...
         for &event in world.contact_events() {
-            match event {
-                ContactEvent::Started(a, b) => {
-                    insert_event(&self.entities, &mut contacts, a, b, true);
-                    insert_event(&self.entities, &mut contacts, b, a, true);
-                }
-                ContactEvent::Stopped(a, b) => {
-                    insert_event(&self.entities, &mut contacts, a, b, false);
-                    insert_event(&self.entities, &mut contacts, b, a, false);
+            let (a, b, started) = match event {
+                ContactEvent::Started(a, b) => (a, b, true),
+                ContactEvent::Stopped(a, b) => (a, b, false),
+            };
+
+            for &(handle, other) in [(a, b), (b, a)].iter() {
+                if let Some(entity) = context.entity(handle) {
+                    let data = create_contact(context, handle, other, started, entity);
+                    active_contacts.apply(&data);
+                    contact_events.single_write(data);
                 }
             }
         }
-
-        self.entities.clear();
-    }
-}
-
-fn insert_event(
-    entities: &HashMap<CollisionObjectSlabHandle, Entity>,
-    contacts: &mut WriteStorage<'_, Contact>,
-    handle: CollisionObjectSlabHandle,
-    other: CollisionObjectSlabHandle,
-    started: bool,
-) {
-    if let Some(&entity) = entities.get(&handle) {
-        if let Some(contact) = contacts.get_mut(entity) {
-            contact
-                .contacts
-                .push(create_contact(entities, handle, other, started, entity));
-        } else {
-            contacts
-                .insert(
-                    entity,
-                    Contact {
-                        contacts: smallvec![create_contact(
-                            entities, handle, other, started, entity
-                        )],
-                    },
-                )
-                .unwrap();
-        }
     }
 }
 
 fn create_contact(
-    entities: &HashMap<CollisionObjectSlabHandle, Entity>,
+    context: &CollisionContext,
     handle: CollisionObjectSlabHandle,
     other: CollisionObjectSlabHandle,
     started: bool,
@@ -107,14 +91,14 @@ fn create_contact(
             you_handle: handle,
             other_handle: other,
             you: entity,
-            other: entities.get(&other).map(ToOwned::to_owned),
+            other: context.entity(other),
         }
     } else {
         ContactEventData::Stopped {
             you_handle: handle,
             other_handle: other,
             you: entity,
-            other: entities.get(&other).map(ToOwned::to_owned),
+            other: context.entity(other),
         }
     }
 }
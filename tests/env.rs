@@ -0,0 +1,108 @@
+//! Plays a few episodes of `BreakoutEnv` on levels built to end them quickly, checking what the
+//! agent is shown and told along the way.
+
+use amethyst::{
+    core::transform::TransformBundle,
+    input::{InputBundle, StringBindings},
+    prelude::GameDataBuilder,
+};
+use simple_brick_breaker::{
+    config::GameplayConfig,
+    env::{Action, BreakoutEnv, Env, OBSERVED_BALLS},
+    game::{BRICK_COLUMNS, BRICK_POINTS, BRICK_ROWS, HEADLESS_STEP},
+    gameplay_systems,
+    level::{Arena, Campaign, CampaignLevel, Edge, Level},
+};
+use std::path::Path;
+
+/// How long an episode may take to end before the test gives up on it.
+const MAX_SECONDS: f32 = 600.0;
+
+/// A campaign of just the one level, with the same edge all the way around the arena.
+fn campaign(bricks: Vec<String>, edge: Edge) -> Campaign {
+    Campaign {
+        levels: vec![CampaignLevel {
+            file: String::new(),
+            ball_speed: None,
+            lives_bonus: 0,
+            level: Level {
+                bricks,
+                arena: Arena {
+                    left: edge,
+                    top: edge,
+                    right: edge,
+                    bottom: edge,
+                    ..Arena::default()
+                },
+                motions: vec![],
+            },
+        }],
+    }
+}
+
+fn breakout_env(campaign: Campaign) -> amethyst::Result<BreakoutEnv> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let game_data = GameDataBuilder::default()
+        .with_bundle(InputBundle::<StringBindings>::new())?
+        .with_bundle(TransformBundle::new())?;
+    BreakoutEnv::new(
+        &root.join("assets"),
+        gameplay_systems(game_data),
+        GameplayConfig::default(),
+        campaign,
+    )
+}
+
+/// Steps with the paddle left where it is until the episode is over, returning the reward for
+/// every step.
+fn play_out(env: &mut BreakoutEnv) -> Vec<f32> {
+    let mut rewards = vec![];
+    for _ in 0..(MAX_SECONDS / HEADLESS_STEP) as usize {
+        let (_, reward, done) = env.step(Action::Stay);
+        rewards.push(reward);
+        if done {
+            return rewards;
+        }
+    }
+    panic!("The episode didn't end within {} seconds", MAX_SECONDS);
+}
+
+#[test]
+fn reset_observes_the_whole_grid() -> amethyst::Result<()> {
+    let mut env = breakout_env(Campaign::default())?;
+
+    let observation = env.reset(1);
+    assert_eq!(observation.bricks.len(), BRICK_ROWS * BRICK_COLUMNS);
+    assert_eq!(
+        observation.to_vec().len(),
+        2 + 4 * OBSERVED_BALLS + BRICK_ROWS * BRICK_COLUMNS
+    );
+    Ok(())
+}
+
+#[test]
+fn breaking_bricks_is_rewarded_until_the_level_is_cleared() -> amethyst::Result<()> {
+    // with nowhere to lose the ball, it has to clear the row sooner or later
+    let mut env = breakout_env(campaign(vec!["1".repeat(BRICK_COLUMNS)], Edge::Wall))?;
+    env.reset(2);
+
+    let rewards = play_out(&mut env);
+    assert!(rewards.iter().all(|&reward| reward >= 0.0));
+    assert_eq!(
+        rewards.iter().sum::<f32>(),
+        (BRICK_COLUMNS as u32 * BRICK_POINTS) as f32
+    );
+    Ok(())
+}
+
+#[test]
+fn losing_every_life_ends_the_episode() -> amethyst::Result<()> {
+    // every ball is lost as soon as it reaches an edge
+    let mut env = breakout_env(campaign(vec![], Edge::Lethal))?;
+    env.reset(3);
+
+    let rewards = play_out(&mut env);
+    assert!(rewards.iter().all(|&reward| reward <= 0.0));
+    assert!(rewards.iter().sum::<f32>() < 0.0);
+    Ok(())
+}
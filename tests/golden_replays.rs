@@ -0,0 +1,117 @@
+//! Plays each recorded game in `tests/golden` without a window and checks that it ends up exactly
+//! where it did when its expected summary was written. Anything that changes how the game plays
+//! out, like a change to the collision response or the paddle, shows up here.
+//!
+//! Replays carry the gameplay config they were recorded with, and are played through the campaign
+//! in `tests/config` rather than the game's own, so tuning the game doesn't break them.
+//!
+//! A replay without an expected summary fails, so a summary that was never committed can't let
+//! the test pass. To add a replay, or once a change in behaviour is intended, run the tests with
+//! `BLESS_GOLDEN_REPLAYS=1` to write the summaries, and check them in.
+
+use amethyst::{
+    config::Config,
+    core::transform::TransformBundle,
+    input::{InputBundle, StringBindings},
+    prelude::GameDataBuilder,
+};
+use simple_brick_breaker::{
+    game::LaunchOptions,
+    gameplay_systems,
+    headless::{Simulation, Summary},
+    level::Campaign,
+    replay::Replay,
+};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+fn play(root: &Path, replay: Replay) -> amethyst::Result<Summary> {
+    let game_data = GameDataBuilder::default()
+        .with_bundle(InputBundle::<StringBindings>::new())?
+        .with_bundle(TransformBundle::new())?;
+    let mut simulation = Simulation::new(
+        &root.join("assets"),
+        gameplay_systems(game_data),
+        replay.config.clone(),
+        Campaign::load_with_levels(root.join("tests").join("config").join("campaign.ron"))?,
+        LaunchOptions {
+            replay: Some(replay),
+            ..LaunchOptions::default()
+        },
+    )?;
+
+    while !simulation.finished() {
+        simulation.step();
+    }
+    Ok(simulation.summary())
+}
+
+/// `foo.ron` becomes `foo.summary.ron`.
+fn summary_path(replay_path: &Path) -> PathBuf {
+    replay_path.with_extension("summary.ron")
+}
+
+#[test]
+fn golden_replays() -> amethyst::Result<()> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let bless = env::var_os("BLESS_GOLDEN_REPLAYS").is_some();
+
+    let mut replay_paths: Vec<PathBuf> = fs::read_dir(root.join("tests").join("golden"))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    replay_paths.retain(|path| {
+        path.extension()
+            .map_or(false, |extension| extension == "ron")
+            && !path.to_string_lossy().ends_with(".summary.ron")
+    });
+    replay_paths.sort();
+    assert!(
+        !replay_paths.is_empty(),
+        "No golden replays found, see the README for how to record them"
+    );
+
+    let mut diverged = vec![];
+    let mut missing = vec![];
+    for replay_path in replay_paths {
+        let summary = play(root, Replay::load(&replay_path)?)?;
+        let expected_path = summary_path(&replay_path);
+
+        if bless {
+            println!("Writing {}", expected_path.display());
+            summary.write(&expected_path)?;
+            continue;
+        }
+        if !expected_path.exists() {
+            println!("{} has no expected summary", replay_path.display());
+            missing.push(replay_path);
+            continue;
+        }
+
+        let expected = Summary::load(&expected_path)?;
+        if summary != expected {
+            println!(
+                "{} diverged\nexpected: {:#?}\n     got: {:#?}",
+                replay_path.display(),
+                expected,
+                summary
+            );
+            diverged.push(replay_path);
+        }
+    }
+
+    assert!(
+        missing.is_empty(),
+        "{} golden replay(s) have no summary, run with BLESS_GOLDEN_REPLAYS=1 to write them: {:?}",
+        missing.len(),
+        missing
+    );
+    assert!(
+        diverged.is_empty(),
+        "{} golden replay(s) diverged: {:?}",
+        diverged.len(),
+        diverged
+    );
+    Ok(())
+}
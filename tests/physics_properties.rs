@@ -0,0 +1,117 @@
+//! Properties of the bounce arithmetic in `physics` that have to hold whatever a ball runs into.
+
+use amethyst::core::math::{Unit, Vector2};
+use proptest::prelude::*;
+use simple_brick_breaker::{
+    game::{BALL_MAX_ROTATION, BALL_MAX_SPEED},
+    physics::{bounce, deflect, set_speed, Surface},
+};
+use std::f32::consts::PI;
+
+/// How far off of head-on a surface can be and still be hit. Anything closer to a graze than
+/// this leaves too little to tell which side of the surface the ball ends up on.
+const MAX_OFFSET: f32 = PI / 2.0 - 0.01;
+
+fn direction(angle: f32) -> Vector2<f32> {
+    Vector2::new(angle.cos(), angle.sin())
+}
+
+/// Surfaces a ball heading at `angle`, as seen from the surfaces, is running into, given as each
+/// normal's offset from that angle and the surface's depth.
+fn surfaces(angle: f32, contacts: &[(f32, f32)], velocity: Vector2<f32>) -> Vec<Surface> {
+    contacts
+        .iter()
+        .map(|&(offset, depth)| Surface {
+            normal: Unit::new_normalize(direction(angle + offset)),
+            depth,
+            velocity,
+        })
+        .collect()
+}
+
+/// How far past a speed limit rounding can leave a ball.
+const SPEED_TOLERANCE: f32 = 1e-3;
+
+/// The angle between two vectors, in radians.
+fn angle_between(a: Vector2<f32>, b: Vector2<f32>) -> f32 {
+    (a.x * b.y - a.y * b.x).atan2(a.dot(&b)).abs()
+}
+
+fn contacts() -> impl Strategy<Value = Vec<(f32, f32)>> {
+    proptest::collection::vec((-MAX_OFFSET..MAX_OFFSET, 0.0f32..4.0), 1..4)
+}
+
+proptest! {
+    #[test]
+    fn bounces_leave_what_they_hit(
+        speed in 1.0f32..BALL_MAX_SPEED,
+        angle in 0.0f32..2.0 * PI,
+        contacts in contacts(),
+        turn in -1.0f32..1.0,
+        min_angle in 0.0f32..0.5,
+    ) {
+        let velocity = direction(angle) * speed;
+        let surfaces = surfaces(angle, &contacts, Vector2::zeros());
+        let bounce = bounce(velocity, &surfaces);
+        prop_assert!(bounce.velocity.dot(&bounce.normal) <= 0.0);
+
+        let velocity = deflect(&bounce, turn * bounce.max_rotation, min_angle);
+        prop_assert!(
+            velocity.dot(&bounce.normal) <= 0.0,
+            "{:?} heads back into {:?}",
+            velocity,
+            bounce.normal
+        );
+    }
+
+    #[test]
+    fn bounces_never_exceed_the_max_speed(
+        speed in 0.0f32..BALL_MAX_SPEED,
+        angle in 0.0f32..2.0 * PI,
+        contacts in contacts(),
+        surface_speed in 0.0f32..BALL_MAX_SPEED,
+        surface_angle in 0.0f32..2.0 * PI,
+        turn in -1.0f32..1.0,
+        min_angle in 0.0f32..0.5,
+    ) {
+        // heading into the surfaces as seen from them, however fast they are moving
+        let surface_velocity = direction(surface_angle) * surface_speed;
+        let velocity = direction(angle) * speed + surface_velocity;
+        let surfaces = surfaces(angle, &contacts, surface_velocity);
+
+        let bounce = bounce(velocity, &surfaces);
+        prop_assert!(bounce.velocity.norm() <= BALL_MAX_SPEED + SPEED_TOLERANCE);
+
+        let velocity = deflect(&bounce, turn * bounce.max_rotation, min_angle);
+        prop_assert!(
+            velocity.norm() <= BALL_MAX_SPEED + SPEED_TOLERANCE,
+            "{} is too fast",
+            velocity.norm()
+        );
+    }
+
+    #[test]
+    fn rotation_stays_within_the_max(
+        speed in 1.0f32..BALL_MAX_SPEED,
+        angle in 0.0f32..2.0 * PI,
+        contacts in contacts(),
+        turn in -1.0f32..1.0,
+    ) {
+        let velocity = direction(angle) * speed;
+        let bounce = bounce(velocity, &surfaces(angle, &contacts, Vector2::zeros()));
+        prop_assert!(bounce.max_rotation >= 0.0 && bounce.max_rotation <= BALL_MAX_ROTATION);
+
+        let velocity = deflect(&bounce, turn * bounce.max_rotation, 0.0);
+        prop_assert!(angle_between(bounce.velocity, velocity) <= BALL_MAX_ROTATION + 1e-4);
+    }
+
+    #[test]
+    fn set_speed_never_exceeds_the_max(
+        x in -1000.0f32..1000.0,
+        y in -1000.0f32..1000.0,
+        speed in 0.0f32..2.0 * BALL_MAX_SPEED,
+    ) {
+        let velocity = set_speed(Vector2::new(x, y), speed);
+        prop_assert!(velocity.norm() <= speed.min(BALL_MAX_SPEED) + SPEED_TOLERANCE);
+    }
+}